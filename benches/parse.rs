@@ -0,0 +1,60 @@
+//! `cargo bench --bench parse`, the table parser on a saved page against the `select` based
+//! parser it replaced. `LIFESPAN_BENCH_PAGE` points at another page, a fresh save of the
+//! article for instance.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use select::document::Document;
+use select::predicate::{Class, Name};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::hint::black_box;
+
+const PAGE: &str = include_str!("fixtures/life_expectancy.html");
+
+// As `parse_html` read the page with `select`: the fourth wikitable, two header rows skipped
+fn parse_with_select(html: &str) -> HashMap<String, [f64; 3]> {
+    let mut result = HashMap::new();
+    let document = Document::from(html);
+    let Some(table) = document.find(Class("wikitable")).nth(3) else {
+        return result;
+    };
+    let Some(tbody) = table.find(Name("tbody")).next() else {
+        return result;
+    };
+    for tr in tbody.find(Name("tr")).skip(2) {
+        let mut tds = tr.find(Name("td")).take(4);
+        let Some(name) = tds.next().and_then(|td| td.find(Name("a")).next()) else {
+            continue;
+        };
+        let mut value = || tds.next()?.text().trim().parse::<f64>().ok();
+        if let (Some(all), Some(male), Some(female)) = (value(), value(), value()) {
+            result.insert(name.text(), [all, male, female]);
+        }
+    }
+
+    result
+}
+
+fn parse(c: &mut Criterion) {
+    let page = match env::var("LIFESPAN_BENCH_PAGE") {
+        Ok(path) => fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path, err)),
+        Err(_) => PAGE.to_string(),
+    };
+    let countries = lifespan_crawler::parse_html(&page).expect("the page has no table to parse");
+    // "Common" is added on top of the rows
+    assert_eq!(
+        parse_with_select(&page).len() + 1,
+        countries.countries().len()
+    );
+
+    let mut group = c.benchmark_group("parse_html");
+    group.bench_function("scraper", |b| {
+        b.iter(|| lifespan_crawler::parse_html(black_box(&page)))
+    });
+    group.bench_function("select", |b| b.iter(|| parse_with_select(black_box(&page))));
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
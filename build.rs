@@ -0,0 +1,117 @@
+use serde_json::Value;
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+// Generates the `Country` enum from `iso3166.json`, only when the `country-enum` feature is on.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=iso3166.json");
+    if env::var_os("CARGO_FEATURE_COUNTRY_ENUM").is_none() {
+        return;
+    }
+
+    let json = fs::read_to_string("iso3166.json").expect("iso3166.json is readable");
+    let entries: Vec<Value> = serde_json::from_str(&json).expect("iso3166.json is valid");
+
+    let mut variants = Vec::new();
+    let mut table = String::new();
+    for entry in &entries {
+        let field = |key: &str| entry.get(key).and_then(Value::as_str);
+        let name = natural_name(
+            field("common_name")
+                .or(field("name"))
+                .expect("every entry has a name"),
+        );
+        let variant = variant_name(&name);
+        assert!(
+            !variants.contains(&variant),
+            "{} is generated twice",
+            variant
+        );
+        writeln!(
+            table,
+            "    Row {{ country: Country::{}, alpha2: {:?}, alpha3: {:?}, name: {:?}, iso_name: {:?}, official_name: {:?} }},",
+            variant,
+            field("alpha2").expect("every entry has an alpha-2 code"),
+            field("alpha3").expect("every entry has an alpha-3 code"),
+            name,
+            field("name").expect("every entry has a name"),
+            field("official_name"),
+        )
+        .unwrap();
+        variants.push(variant);
+    }
+
+    let mut code = String::new();
+    writeln!(
+        code,
+        "/// Every ISO 3166-1 country, generated from `iso3166.json`."
+    )
+    .unwrap();
+    writeln!(
+        code,
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]"
+    )
+    .unwrap();
+    writeln!(code, "pub enum Country {{").unwrap();
+    for variant in &variants {
+        writeln!(code, "    {},", variant).unwrap();
+    }
+    writeln!(code, "}}\n").unwrap();
+    writeln!(code, "// Same order as the variants").unwrap();
+    writeln!(code, "const TABLE: &[Row] = &[\n{}];", table).unwrap();
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("country.rs");
+    fs::write(out, code).expect("OUT_DIR is writable");
+}
+
+// "Virgin Islands, British" → "British Virgin Islands", "Congo, The Democratic Republic of the" →
+// "Democratic Republic of the Congo", other names as they are
+fn natural_name(name: &str) -> String {
+    match name.split_once(", ") {
+        Some((place, qualifier))
+            if qualifier.ends_with(" of")
+                || qualifier.ends_with(" of the")
+                || !qualifier.contains(' ') =>
+        {
+            let qualifier = qualifier.strip_prefix("The ").unwrap_or(qualifier);
+            format!("{} {}", qualifier, place)
+        }
+        _ => name.to_string(),
+    }
+}
+
+// "British Virgin Islands" → "BritishVirginIslands", "Côte d'Ivoire" → "CoteDIvoire"
+fn variant_name(name: &str) -> String {
+    // Parentheticals only repeat or qualify the name, "Cocos (Keeling) Islands"
+    let mut name = name.to_string();
+    while let (Some(start), Some(end)) = (name.find(" ("), name.find(')')) {
+        name.replace_range(start..=end, "");
+    }
+
+    name.split([' ', '-', '\'', ','])
+        .map(|word| {
+            let word: String = word.chars().filter_map(fold).collect();
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// The few non-ASCII letters found in the English names
+fn fold(c: char) -> Option<char> {
+    match c {
+        'Å' => Some('A'),
+        'é' => Some('e'),
+        'ô' => Some('o'),
+        'ç' => Some('c'),
+        'ü' => Some('u'),
+        c if c.is_ascii_alphanumeric() => Some(c),
+        _ => None,
+    }
+}
@@ -0,0 +1,8 @@
+//! `cargo +nightly fuzz run parse_html`, the page is untrusted remote content.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    lifespan_crawler::fuzzing::parse_page(data);
+});
@@ -0,0 +1,458 @@
+use crate::date::iso_date;
+use crate::provenance;
+use crate::schema::parse_strict;
+use crate::usage;
+use crate::{
+    get_tmp_dir, get_tmp_file_path, is_intact, quarantine, read_tmp_file, write_tmp_file,
+    CountryInfo, STORAGE_DIR,
+};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::SystemTime;
+
+/// Persistence for fetched data, `load` returns `None` on a cache miss.
+pub trait CacheStore: Send + Sync {
+    fn load(&self) -> Result<Option<HashMap<String, CountryInfo>>>;
+    fn store(&self, data: &HashMap<String, CountryInfo>) -> Result<()>;
+
+    /// Single entry lookup, stores with an index override this to avoid loading everything.
+    fn get(&self, country: &str) -> Result<Option<CountryInfo>> {
+        Ok(self.load()?.and_then(|mut data| data.remove(country)))
+    }
+
+    /// When the data was last stored, `None` when empty or when the store doesn't know.
+    fn modified(&self) -> Result<Option<SystemTime>> {
+        Ok(None)
+    }
+
+    /// Where a client archives every snapshot it stores, and looks years up, see
+    /// `Client::get_data_for_year`. `None` for stores that keep no history.
+    fn snapshots(&self) -> Option<SnapshotCache> {
+        None
+    }
+
+    /// File where a client records the provenance of what it stores, see `Client::provenance`.
+    /// `None` for stores that keep nothing on disk.
+    fn provenance_path(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+// `snapshots/` next to a cache file
+fn snapshots_beside(path: &Path) -> Option<SnapshotCache> {
+    Some(SnapshotCache::new(path.parent()?.join("snapshots")))
+}
+
+fn provenance_beside(path: &Path) -> Option<PathBuf> {
+    Some(path.parent()?.join(provenance::FILE_NAME))
+}
+
+fn modified_at(path: &Path) -> Result<Option<SystemTime>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    Ok(Some(fs::metadata(path)?.modified()?))
+}
+
+/// JSON file cache, `~/.config/live_progress/.tmp_expectancy.json` by default.
+#[derive(Debug, Clone)]
+pub struct FileCache {
+    path: PathBuf,
+}
+
+impl FileCache {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileCache { path: path.into() }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+impl Default for FileCache {
+    fn default() -> Self {
+        FileCache::new(get_tmp_file_path())
+    }
+}
+
+impl CacheStore for FileCache {
+    fn load(&self) -> Result<Option<HashMap<String, CountryInfo>>> {
+        read_tmp_file(&self.path)
+    }
+
+    fn store(&self, data: &HashMap<String, CountryInfo>) -> Result<()> {
+        write_tmp_file(&self.path, data)
+    }
+
+    fn modified(&self) -> Result<Option<SystemTime>> {
+        modified_at(&self.path)
+    }
+
+    fn snapshots(&self) -> Option<SnapshotCache> {
+        snapshots_beside(&self.path)
+    }
+
+    fn provenance_path(&self) -> Option<PathBuf> {
+        provenance_beside(&self.path)
+    }
+}
+
+/// Cache living in the process only, for hosts without a writable filesystem, see `ClientBuilder::in_memory`.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    data: Mutex<Option<(HashMap<String, CountryInfo>, SystemTime)>>,
+}
+
+impl CacheStore for MemoryCache {
+    fn load(&self) -> Result<Option<HashMap<String, CountryInfo>>> {
+        let data = self.data.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(data.as_ref().map(|(data, _)| data.clone()))
+    }
+
+    fn store(&self, data: &HashMap<String, CountryInfo>) -> Result<()> {
+        let mut stored = self.data.lock().unwrap_or_else(PoisonError::into_inner);
+        *stored = Some((data.clone(), SystemTime::now()));
+        Ok(())
+    }
+
+    fn modified(&self) -> Result<Option<SystemTime>> {
+        let data = self.data.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(data.as_ref().map(|(_, modified)| *modified))
+    }
+}
+
+/// Keyed cache with one `"name"\t{entry}` line per country, sorted by name,
+/// so a single lookup only deserializes the matching line.
+/// `~/.config/live_progress/.tmp_expectancy.idx` by default.
+#[derive(Debug, Clone)]
+pub struct IndexedCache {
+    path: PathBuf,
+}
+
+impl IndexedCache {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        IndexedCache { path: path.into() }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    fn lines(&self) -> Result<Option<impl Iterator<Item = std::io::Result<String>>>> {
+        if !self.path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(BufReader::new(File::open(&self.path)?).lines()))
+    }
+}
+
+impl Default for IndexedCache {
+    fn default() -> Self {
+        IndexedCache::new(get_tmp_dir().join(".tmp_expectancy.idx"))
+    }
+}
+
+impl CacheStore for IndexedCache {
+    fn load(&self) -> Result<Option<HashMap<String, CountryInfo>>> {
+        let Some(lines) = self.lines()? else {
+            return Ok(None);
+        };
+        let mut data = HashMap::new();
+        for line in lines {
+            let line = line?;
+            let parsed = line
+                .split_once('\t')
+                .ok_or_else(|| "line without a tab".to_string())
+                .and_then(|(key, entry)| {
+                    let key: String = serde_json::from_str(key).map_err(|err| err.to_string())?;
+                    if key.trim().is_empty() {
+                        return Err("blank key".to_string());
+                    }
+                    Ok((key, parse_strict(entry)?))
+                });
+            let (key, entry) = match parsed {
+                Ok(parsed) => parsed,
+                Err(reason) => {
+                    quarantine(&self.path, &reason)?;
+                    return Ok(None);
+                }
+            };
+            data.insert(key, entry);
+        }
+
+        Ok(Some(data))
+    }
+
+    fn store(&self, data: &HashMap<String, CountryInfo>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut entries: Vec<_> = data.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+
+        // JSON-encoded keys cannot contain a raw tab or newline
+        let mut output = BufWriter::new(File::create(&self.path)?);
+        for (key, entry) in entries {
+            writeln!(
+                output,
+                "{}\t{}",
+                serde_json::to_string(key)?,
+                serde_json::to_string(entry)?
+            )?;
+        }
+        output.flush()?;
+
+        Ok(())
+    }
+
+    fn get(&self, country: &str) -> Result<Option<CountryInfo>> {
+        let Some(lines) = self.lines()? else {
+            return Ok(None);
+        };
+        let key = serde_json::to_string(country)?;
+        for line in lines {
+            let line = line?;
+            if let Some(entry) = line
+                .strip_prefix(key.as_str())
+                .and_then(|rest| rest.strip_prefix('\t'))
+            {
+                return match parse_strict(entry) {
+                    Ok(entry) => Ok(Some(entry)),
+                    Err(reason) => {
+                        quarantine(&self.path, &reason)?;
+                        Ok(None)
+                    }
+                };
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn modified(&self) -> Result<Option<SystemTime>> {
+        modified_at(&self.path)
+    }
+
+    fn snapshots(&self) -> Option<SnapshotCache> {
+        snapshots_beside(&self.path)
+    }
+
+    fn provenance_path(&self) -> Option<PathBuf> {
+        provenance_beside(&self.path)
+    }
+}
+
+/// Keeps every stored snapshot as `YYYY-MM-DD.json` in a directory instead of overwriting,
+/// `load` answering with the most recent one.
+/// `~/.config/live_progress/snapshots/` by default, where a client with the default
+/// `FileCache` archives what it fetches.
+#[derive(Debug, Clone)]
+pub struct SnapshotCache {
+    dir: PathBuf,
+}
+
+impl SnapshotCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        SnapshotCache { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    /// Dates of the stored snapshots, oldest first.
+    pub fn dates(&self) -> Result<Vec<String>> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut dates: Vec<String> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name().into_string().ok()?;
+                let date = name.strip_suffix(".json")?;
+                is_date(date).then(|| date.to_string())
+            })
+            .collect();
+        dates.sort();
+
+        Ok(dates)
+    }
+
+    /// Snapshot stored on `date`, given as `YYYY-MM-DD`.
+    pub fn load_date(&self, date: &str) -> Result<Option<HashMap<String, CountryInfo>>> {
+        read_tmp_file(&self.dir.join(format!("{}.json", date)))
+    }
+
+    /// Last snapshot stored during `year`, with its date.
+    pub fn load_year(&self, year: u32) -> Result<Option<(String, HashMap<String, CountryInfo>)>> {
+        let prefix = format!("{:04}-", year);
+        for date in self.dates()?.into_iter().rev() {
+            if date.starts_with(&prefix) {
+                if let Some(data) = self.load_date(&date)? {
+                    return Ok(Some((date, data)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Default for SnapshotCache {
+    fn default() -> Self {
+        SnapshotCache::new(get_tmp_dir().join("snapshots"))
+    }
+}
+
+impl CacheStore for SnapshotCache {
+    fn load(&self) -> Result<Option<HashMap<String, CountryInfo>>> {
+        for date in self.dates()?.into_iter().rev() {
+            if let Some(data) = self.load_date(&date)? {
+                return Ok(Some(data));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Stored under today's date (UTC), replacing a snapshot taken earlier the same day.
+    fn store(&self, data: &HashMap<String, CountryInfo>) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        write_tmp_file(
+            &self
+                .dir
+                .join(format!("{}.json", iso_date(SystemTime::now()))),
+            data,
+        )
+    }
+
+    fn modified(&self) -> Result<Option<SystemTime>> {
+        match self.dates()?.last() {
+            Some(date) => modified_at(&self.dir.join(format!("{}.json", date))),
+            None => Ok(None),
+        }
+    }
+
+    fn snapshots(&self) -> Option<SnapshotCache> {
+        Some(self.clone())
+    }
+
+    fn provenance_path(&self) -> Option<PathBuf> {
+        Some(self.dir.join(provenance::FILE_NAME))
+    }
+}
+
+fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(index, byte)| match index {
+            4 | 7 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
+/// Directory holding every cache file, `~/.config/live_progress`, or `%LOCALAPPDATA%\live_progress` on Windows,
+/// unless set with `set_cache_dir`.
+pub fn cache_dir() -> PathBuf {
+    get_tmp_dir()
+}
+
+/// Moves every cache file of the process under `dir`, for hosts without a writable home directory such as iOS and Android apps.
+/// Call it before anything is cached, it fails when a different directory was already set.
+/// A single store can instead be replaced through `ClientBuilder::cache`.
+pub fn set_cache_dir(dir: impl Into<PathBuf>) -> Result<()> {
+    let dir = dir.into();
+    let current = STORAGE_DIR.get_or_init(|| dir.clone());
+    if *current != dir {
+        return Err(anyhow!(
+            "The cache directory is already set to {}",
+            current.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// A file under the cache directory, see `cache_files`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Where the data came from, guessed from the file name.
+    pub source: &'static str,
+    /// Whether the checksum matches, `None` for files stored without one.
+    pub intact: Option<bool>,
+}
+
+/// Every file under `cache_dir`, snapshots included, sorted by path. The usage counters are not cache data and left out.
+pub fn cache_files() -> Result<Vec<CacheFile>> {
+    let dir = cache_dir();
+    let mut paths = Vec::new();
+    for subdir in [dir.clone(), dir.join("snapshots")] {
+        if !subdir.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(subdir)? {
+            let path = entry?.path();
+            if path.is_file() && path != dir.join(usage::FILE_NAME) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let metadata = fs::metadata(&path)?;
+            let source = source_of(&path);
+            let checksummed = path.extension().is_some_and(|ext| ext == "json");
+            Ok(CacheFile {
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+                source,
+                intact: checksummed.then(|| is_intact(&path)),
+                path,
+            })
+        })
+        .collect()
+}
+
+fn source_of(path: &Path) -> &'static str {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let in_snapshots = path
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .is_some_and(|parent| parent == "snapshots");
+    match name {
+        _ if name.ends_with(".corrupt") => "quarantined",
+        _ if in_snapshots => "Wikipedia snapshot",
+        ".tmp_expectancy.json"
+        | ".tmp_expectancy.idx"
+        | ".tmp_regions.json"
+        | provenance::FILE_NAME => "Wikipedia",
+        ".tmp_history.json" => "WHO Global Health Observatory",
+        _ if name.starts_with(".tmp_subnational_") => "Wikipedia subnational",
+        _ if name.starts_with(".tmp_cohort_") => "UN World Population Prospects",
+        _ => "unknown",
+    }
+}
+
+/// Removes every file listed by `cache_files`, returning how many were removed.
+pub fn clear_cache() -> Result<usize> {
+    let files = cache_files()?;
+    for file in &files {
+        fs::remove_file(&file.path)?;
+    }
+
+    Ok(files.len())
+}
@@ -0,0 +1,60 @@
+const SPARKS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const BAR: char = '█';
+const PARTIAL_BARS: &[char] = &[' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Horizontal bar chart, bars scaled between the lowest and highest value so small gaps stay visible.
+pub fn bar_chart(rows: &[(String, f64)], width: usize) -> String {
+    let label_width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let (min, max) = bounds(rows.iter().map(|(_, value)| *value));
+    // Keep a minimal bar for the lowest value
+    let floor = min - (max - min) * 0.1;
+
+    let mut output = String::new();
+    for (label, value) in rows {
+        let ratio = if max > floor {
+            (value - floor) / (max - floor)
+        } else {
+            1.0
+        };
+        let eighths = (ratio * width as f64 * 8.0).round() as usize;
+        let mut bar: String = std::iter::repeat_n(BAR, eighths / 8).collect();
+        if !eighths.is_multiple_of(8) {
+            bar.push(PARTIAL_BARS[eighths % 8]);
+        }
+        output.push_str(&format!(
+            "{:<label_width$} {:<width$} {:.2}\n",
+            label,
+            bar,
+            value,
+            label_width = label_width,
+            width = width + 1,
+        ));
+    }
+
+    output
+}
+
+pub fn sparkline(values: &[f64]) -> String {
+    let (min, max) = bounds(values.iter().copied());
+    values
+        .iter()
+        .map(|value| {
+            if max > min {
+                let index = ((value - min) / (max - min) * (SPARKS.len() - 1) as f64).round();
+                SPARKS[index as usize]
+            } else {
+                SPARKS[SPARKS.len() / 2]
+            }
+        })
+        .collect()
+}
+
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+        (min.min(value), max.max(value))
+    })
+}
@@ -0,0 +1,815 @@
+use crate::cache::{CacheStore, FileCache, MemoryCache, SnapshotCache};
+use crate::countries;
+use crate::export::Attribution;
+use crate::progress::{Phase, Progress};
+use crate::provenance::{self, Provenance};
+#[cfg(feature = "defaults-file")]
+use crate::receive_default_expectancy;
+use crate::schedule::Schedule;
+use crate::source::DataSource;
+#[cfg(not(feature = "blocking"))]
+use crate::source::OfflineSource;
+#[cfg(feature = "blocking")]
+use crate::source::WikipediaSource;
+#[cfg(feature = "blocking")]
+use crate::tables::TableSelector;
+use crate::transform::Transform;
+#[cfg(feature = "blocking")]
+use crate::transport::Transport;
+use crate::usage::{self, Event};
+#[cfg(feature = "blocking")]
+use crate::{download_default_expectancy, RELEASES_URL};
+use crate::{
+    embedded_default_expectancy, error_kind, CountryInfo, Dataset, Error, ErrorKind, Precision,
+    COMMON,
+};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+#[cfg(feature = "blocking")]
+use std::sync::Arc;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+
+pub struct Client {
+    source: Box<dyn DataSource>,
+    // The source when it is the article, for `FetchOptions::revision` and `timeout`
+    #[cfg(feature = "blocking")]
+    wikipedia: Option<WikipediaSource>,
+    cache: Box<dyn CacheStore>,
+    snapshots: Option<SnapshotCache>,
+    // Beside the cache, see `CacheStore::provenance_path`
+    provenance_path: Option<PathBuf>,
+    progress: Option<Box<Progress>>,
+    precision: Precision,
+    policy: FallbackPolicy,
+    in_memory: bool,
+    countries_only: bool,
+    transforms: Vec<Box<dyn Transform>>,
+    #[cfg(feature = "blocking")]
+    defaults_url: Option<String>,
+    fallback: Vec<Origin>,
+    stats: Mutex<CacheStats>,
+    // Of this client's last fetch
+    provenance: Mutex<Option<Provenance>>,
+}
+
+/// Where an answer of the client came from, the layers of its fallback chain.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    Cache,
+    /// Fetched from the source, then cached.
+    Network,
+    /// `default_expectancy.json` in the working directory, or its release download, see
+    /// `ClientBuilder::release_defaults`.
+    BundledFile,
+    /// The defaults compiled into the binary.
+    Embedded,
+}
+
+/// What the client does once a fetch fails, see `ClientBuilder::fallback_policy`.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackPolicy {
+    /// Fails with the fetch's error, trying no other layer. A cache tried before the network
+    /// still answers, but nothing older is ever served.
+    FailFast,
+    /// Tries the layers after the network but the defaults, then any data the chain skipped:
+    /// the cache when the chain leaves it out, else the newest snapshot archived beside it,
+    /// however old. Fails with `Error::StaleOnly` when there is none.
+    PreferStaleCache,
+    /// Tries every layer after the network, the bundled and embedded defaults included.
+    #[default]
+    AllowDefaults,
+}
+
+impl FromStr for FallbackPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "failfast" => Ok(FallbackPolicy::FailFast),
+            "preferstalecache" => Ok(FallbackPolicy::PreferStaleCache),
+            "allowdefaults" => Ok(FallbackPolicy::AllowDefaults),
+            _ => Err(anyhow!("Unknown fallback policy: {}", s)),
+        }
+    }
+}
+
+/// Order the layers are tried in unless `ClientBuilder::fallback` changes it.
+pub const DEFAULT_FALLBACK: [Origin; 4] = [
+    Origin::Cache,
+    Origin::Network,
+    Origin::BundledFile,
+    Origin::Embedded,
+];
+
+impl Origin {
+    /// Whether the data is the offline defaults rather than the source's.
+    pub fn is_default(self) -> bool {
+        matches!(self, Origin::BundledFile | Origin::Embedded)
+    }
+}
+
+impl FromStr for Origin {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "cache" => Ok(Origin::Cache),
+            "network" => Ok(Origin::Network),
+            "bundledfile" | "file" => Ok(Origin::BundledFile),
+            "embedded" => Ok(Origin::Embedded),
+            _ => Err(anyhow!("Unknown layer: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Cache => write!(f, "cache"),
+            Origin::Network => write!(f, "network"),
+            Origin::BundledFile => write!(f, "bundled file"),
+            Origin::Embedded => write!(f, "embedded defaults"),
+        }
+    }
+}
+
+/// Cache lookups of a client since it was built, see `Client::cache_stats`.
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// When the cache was last written, by any client or process.
+    pub last_refresh: Option<SystemTime>,
+    /// Origin of the last answer, `None` before the first one.
+    pub last_source: Option<Origin>,
+    /// How this client's last fetch went, `None` before the first one.
+    pub last_fetch: Option<FetchOutcome>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct FetchOutcome {
+    pub at: SystemTime,
+    /// Why it failed, `None` when it succeeded.
+    pub error: Option<String>,
+}
+
+/// How `Client::fetch_with` retrieves the data, through the client's source and into its cache
+/// unless told otherwise.
+#[derive(Default)]
+pub struct FetchOptions {
+    source: Option<Box<dyn DataSource>>,
+    #[cfg(feature = "blocking")]
+    timeout: Option<Duration>,
+    #[cfg(feature = "blocking")]
+    revision: Option<u64>,
+    no_cache: bool,
+}
+
+impl FetchOptions {
+    pub fn new() -> Self {
+        FetchOptions::default()
+    }
+
+    /// Fetches from `source` instead of the client's.
+    pub fn source(mut self, source: impl DataSource + 'static) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// See `WikipediaSource::timeout`, for a client reading the article.
+    #[cfg(feature = "blocking")]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// See `WikipediaSource::revision`, for a client reading the article.
+    #[cfg(feature = "blocking")]
+    pub fn revision(mut self, id: u64) -> Self {
+        self.revision = Some(id);
+        self
+    }
+
+    /// Leaves the cache and the recorded provenance as they are.
+    pub fn no_cache(mut self) -> Self {
+        self.no_cache = true;
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct ClientBuilder {
+    source: Option<Box<dyn DataSource>>,
+    // Used when no `source` is set
+    #[cfg(feature = "blocking")]
+    wikipedia: WikipediaSource,
+    cache: Option<Box<dyn CacheStore>>,
+    snapshots: Option<SnapshotCache>,
+    progress: Option<Box<Progress>>,
+    precision: Precision,
+    policy: FallbackPolicy,
+    in_memory: bool,
+    countries_only: bool,
+    transforms: Vec<Box<dyn Transform>>,
+    #[cfg(feature = "blocking")]
+    defaults_url: Option<String>,
+    fallback: Option<Vec<Origin>>,
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Client::builder().build()
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Cached data if any, otherwise fetched data, falling back to the bundled defaults on network errors.
+    /// Being rate limited fails with `Error::RateLimited` instead.
+    pub fn get_data(&self) -> Result<HashMap<String, CountryInfo>> {
+        Ok(self.get_data_with_origin()?.0)
+    }
+
+    /// Same as `get_data`, along with the layer of the fallback chain that answered, so an app
+    /// can tell it is showing the offline defaults.
+    pub fn get_data_with_origin(&self) -> Result<(HashMap<String, CountryInfo>, Origin)> {
+        let (data, origin) = self.load()?;
+        Ok((self.present(data), origin))
+    }
+
+    /// Layers `get_data` tries, in order.
+    pub fn fallback(&self) -> &[Origin] {
+        &self.fallback
+    }
+
+    /// Values for `year`, from the WHO history when the `history` feature is enabled,
+    /// otherwise from the last snapshot archived that year, see `ClientBuilder::snapshots`.
+    /// An in-memory client fetches the history every time.
+    pub fn get_data_for_year(&self, year: u32) -> Result<HashMap<String, CountryInfo>> {
+        #[cfg(feature = "history")]
+        {
+            let history = if self.in_memory {
+                crate::history::fetch_history()
+            } else {
+                crate::history::get_history()
+            };
+            if let Ok(history) = history {
+                let data = crate::history::year_of(&history, year);
+                if !data.is_empty() {
+                    return Ok(self.present(data));
+                }
+            }
+        }
+        if let Some(snapshots) = &self.snapshots {
+            if let Some((_, data)) = snapshots.load_year(year)? {
+                return Ok(self.present(data));
+            }
+        }
+
+        Err(anyhow!("No data for {}", year))
+    }
+
+    /// Fetches from the source and replaces the cache, without falling back to the bundled defaults.
+    pub fn refresh(&self) -> Result<HashMap<String, CountryInfo>> {
+        let (data, provenance) = self.fetch()?;
+        self.store(&data, provenance)?;
+        self.update_stats(|stats| stats.last_source = Some(Origin::Network));
+        info!(entries = data.len(), "refreshed");
+        Ok(self.present(data))
+    }
+
+    /// Fetches as `options` say, without looking at the cache or falling back to the defaults.
+    /// The client's transforms still apply.
+    pub fn fetch_with(&self, options: FetchOptions) -> Result<HashMap<String, CountryInfo>> {
+        #[cfg(feature = "blocking")]
+        let pinned = match (options.timeout, options.revision) {
+            (None, None) => None,
+            _ if options.source.is_some() => {
+                return Err(anyhow!(
+                    "A timeout or revision applies to the client's source"
+                ))
+            }
+            (timeout, revision) => {
+                let mut wikipedia = self
+                    .wikipedia
+                    .clone()
+                    .ok_or_else(|| anyhow!("A timeout or revision needs the Wikipedia source"))?;
+                if let Some(timeout) = timeout {
+                    wikipedia = wikipedia.timeout(timeout);
+                }
+                if let Some(revision) = revision {
+                    wikipedia = wikipedia.revision(revision);
+                }
+                Some(wikipedia)
+            }
+        };
+        #[cfg(feature = "blocking")]
+        let source = match &pinned {
+            Some(wikipedia) => wikipedia,
+            None => options.source.as_deref().unwrap_or(self.source.as_ref()),
+        };
+        #[cfg(not(feature = "blocking"))]
+        let source = options.source.as_deref().unwrap_or(self.source.as_ref());
+
+        let (data, provenance) = self.fetch_from(source)?;
+        if !options.no_cache {
+            self.store(&data, provenance)?;
+            self.update_stats(|stats| stats.last_source = Some(Origin::Network));
+        }
+        Ok(self.present(data))
+    }
+
+    /// Refreshes when a publication window of `schedule` opened since the cache was last written.
+    pub fn refresh_if_due(
+        &self,
+        schedule: &Schedule,
+    ) -> Result<Option<HashMap<String, CountryInfo>>> {
+        if !schedule.is_due(self.cache.modified()?, SystemTime::now()) {
+            return Ok(None);
+        }
+        Ok(Some(self.refresh()?))
+    }
+
+    /// Refreshes when the cache was last written more than `max_age` ago, or never, returning
+    /// whether it did. A cache written in the future, after a clock change, counts as fresh.
+    pub fn refresh_if_older_than(&self, max_age: Duration) -> Result<bool> {
+        Ok(self.refresh_if_stale(max_age)?.is_some())
+    }
+
+    // Same as `refresh_if_older_than`, with the refreshed data
+    pub(crate) fn refresh_if_stale(
+        &self,
+        max_age: Duration,
+    ) -> Result<Option<HashMap<String, CountryInfo>>> {
+        if self.data_age()?.is_some_and(|age| age <= max_age) {
+            return Ok(None);
+        }
+        Ok(Some(self.refresh()?))
+    }
+
+    /// What the cache holds, without fetching.
+    pub fn cached(&self) -> Result<Option<HashMap<String, CountryInfo>>> {
+        Ok(self.cache.load()?.map(|data| self.present(data)))
+    }
+
+    /// When the cache was last written, if the store keeps track. Same as `last_updated`.
+    pub fn last_refresh(&self) -> Result<Option<SystemTime>> {
+        self.last_updated()
+    }
+
+    /// When the cached data was fetched, or imported. `None` with an empty cache or a store
+    /// that doesn't keep track.
+    pub fn last_updated(&self) -> Result<Option<SystemTime>> {
+        self.cache.modified()
+    }
+
+    /// How old the cached data is, for "data from 12 days ago". Zero when it was written in the
+    /// future, after a clock change.
+    pub fn data_age(&self) -> Result<Option<Duration>> {
+        Ok(self.last_updated()?.map(|updated| {
+            SystemTime::now()
+                .duration_since(updated)
+                .unwrap_or_default()
+        }))
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        let stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+        CacheStats {
+            last_refresh: self.cache.modified().ok().flatten(),
+            ..stats.clone()
+        }
+    }
+
+    fn update_stats(&self, update: impl FnOnce(&mut CacheStats)) {
+        update(&mut self.stats.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+
+    // Applies `countries_only` and `precision` to an answer
+    fn present(&self, data: HashMap<String, CountryInfo>) -> HashMap<String, CountryInfo> {
+        let data = if self.countries_only {
+            countries::countries_only(data)
+        } else {
+            data
+        };
+        if self.precision == Precision::Full {
+            return data;
+        }
+        data.into_iter()
+            .map(|(name, info)| (name, info.rounded(self.precision)))
+            .collect()
+    }
+
+    // The first layer of the chain with data. Rate limiting ends it, the defaults would hide it
+    fn load(&self) -> Result<(HashMap<String, CountryInfo>, Origin)> {
+        let mut failure = None;
+        for &layer in &self.fallback {
+            let result = match layer {
+                Origin::Cache => self.load_cache(),
+                Origin::Network => self.fetch().and_then(|(data, provenance)| {
+                    self.store(&data, provenance)?;
+                    Ok(Some(data))
+                }),
+                #[cfg(feature = "defaults-file")]
+                Origin::BundledFile => self.bundled_file().map(Some),
+                // Dropped from the chain in `build`
+                #[cfg(not(feature = "defaults-file"))]
+                Origin::BundledFile => Ok(None),
+                Origin::Embedded => embedded_default_expectancy().map(Some),
+            };
+            match result {
+                Ok(Some(data)) => {
+                    if layer.is_default() {
+                        warn!("answering from the {}", layer);
+                        self.record(Event::Fallback);
+                    }
+                    self.update_stats(|stats| stats.last_source = Some(layer));
+                    return Ok((data, layer));
+                }
+                Ok(None) => {}
+                Err(err) if error_kind(&err) == ErrorKind::RateLimited => return Err(err),
+                Err(err) if layer == Origin::Network && self.policy == FallbackPolicy::FailFast => {
+                    return Err(err)
+                }
+                Err(err) => {
+                    warn!("{} failed: {:#}", layer, err);
+                    failure = Some(err);
+                }
+            }
+        }
+
+        let err = failure.unwrap_or_else(|| anyhow!("No data"));
+        if self.policy == FallbackPolicy::PreferStaleCache {
+            if let Some(data) = self.load_stale() {
+                warn!("answering from stale data after: {:#}", err);
+                self.update_stats(|stats| stats.last_source = Some(Origin::Cache));
+                return Ok((data, Origin::Cache));
+            }
+        }
+        if self.policy != FallbackPolicy::AllowDefaults {
+            return Err(err.context(Error::StaleOnly));
+        }
+        Err(err)
+    }
+
+    // `None` on a miss, an unreadable cache counting as one
+    fn load_cache(&self) -> Result<Option<HashMap<String, CountryInfo>>> {
+        match self.cache.load() {
+            Ok(Some(data)) => {
+                debug!(entries = data.len(), "cache hit");
+                self.record(Event::CacheHit);
+                self.update_stats(|stats| stats.hits += 1);
+                return Ok(Some(data));
+            }
+            Ok(None) => debug!("cache miss"),
+            Err(err) => warn!("cache unreadable: {:#}", err),
+        }
+        self.record(Event::CacheMiss);
+        self.update_stats(|stats| stats.misses += 1);
+        Ok(None)
+    }
+
+    // What `PreferStaleCache` answers with once the chain is exhausted. The cache only when the
+    // chain skipped it, a miss there would be one again
+    fn load_stale(&self) -> Option<HashMap<String, CountryInfo>> {
+        if !self.fallback.contains(&Origin::Cache) {
+            if let Some(data) = self.load_cache().ok().flatten() {
+                return Some(data);
+            }
+        }
+        match self.snapshots.as_ref()?.load() {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("snapshots unreadable: {:#}", err);
+                None
+            }
+        }
+    }
+
+    // `default_expectancy.json`, downloaded when there is no trusted one and a release URL is set
+    #[cfg(feature = "defaults-file")]
+    fn bundled_file(&self) -> Result<HashMap<String, CountryInfo>> {
+        let local = receive_default_expectancy();
+        #[cfg(feature = "blocking")]
+        if let (Err(err), Some(url)) = (&local, &self.defaults_url) {
+            warn!("{:#}, downloading the defaults from {}", err, url);
+            return download_default_expectancy(url);
+        }
+        local
+    }
+
+    fn fetch(&self) -> Result<(HashMap<String, CountryInfo>, Option<Provenance>)> {
+        self.fetch_from(self.source.as_ref())
+    }
+
+    fn fetch_from(
+        &self,
+        source: &dyn DataSource,
+    ) -> Result<(HashMap<String, CountryInfo>, Option<Provenance>)> {
+        self.record(Event::Fetch);
+        let result = match &self.progress {
+            Some(progress) => source.fetch_with_provenance(progress.as_ref()),
+            None => source.fetch_with_provenance(&|_, _| {}),
+        }
+        .and_then(|(data, provenance)| Ok((self.transform(data)?, provenance)));
+        if result.is_err() {
+            self.record(Event::FetchFailure);
+        }
+        let outcome = FetchOutcome {
+            at: SystemTime::now(),
+            error: result.as_ref().err().map(|err| format!("{:#}", err)),
+        };
+        self.update_stats(|stats| stats.last_fetch = Some(outcome));
+        result
+    }
+
+    fn transform(
+        &self,
+        data: HashMap<String, CountryInfo>,
+    ) -> Result<HashMap<String, CountryInfo>> {
+        self.transforms
+            .iter()
+            .try_fold(data, |data, transform| transform.apply(data))
+    }
+
+    // See `usage`, an in-memory client keeps off the filesystem
+    fn record(&self, event: Event) {
+        if !self.in_memory {
+            usage::record(event);
+        }
+    }
+
+    fn store(
+        &self,
+        data: &HashMap<String, CountryInfo>,
+        provenance: Option<Provenance>,
+    ) -> Result<()> {
+        self.report(Phase::Caching, 0.0);
+        self.cache.store(data)?;
+        // A `SnapshotCache` store rewrites the file it just wrote, a lost snapshot only
+        // leaves a gap in the history
+        if let Some(snapshots) = &self.snapshots {
+            if let Err(err) = snapshots.store(data) {
+                warn!(
+                    "could not archive a snapshot in {}: {:#}",
+                    snapshots.dir().display(),
+                    err
+                );
+            }
+        }
+        // Only attribution depends on it, the data itself is stored
+        if let Some(path) = &self.provenance_path {
+            if let Err(err) = provenance::store(path, provenance.as_ref()) {
+                warn!(
+                    "could not record the provenance in {}: {:#}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+        *self
+            .provenance
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = provenance;
+        self.report(Phase::Caching, 1.0);
+        Ok(())
+    }
+
+    /// Where the cached data was fetched from, read back from beside the cache when this
+    /// client hasn't fetched yet. `None` when the source couldn't tell or nothing was fetched.
+    pub fn provenance(&self) -> Result<Option<Provenance>> {
+        let fetched = self
+            .provenance
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        match (fetched, &self.provenance_path) {
+            (Some(fetched), _) => Ok(Some(fetched)),
+            (None, Some(path)) => provenance::load_from(path),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Single entry, answered from the cache without loading the whole dataset when the store supports it.
+    pub fn get_country(&self, country: &str) -> Result<Option<CountryInfo>> {
+        if self.countries_only && countries::is_territory(country) {
+            return Ok(None);
+        }
+        // The cached "Common" averages territories too, `present` recomputes it without them
+        let recomputed = self.countries_only && country == COMMON;
+        if recomputed || self.fallback.first() != Some(&Origin::Cache) {
+            return Ok(self.get_data()?.remove(country));
+        }
+        if let Ok(Some(info)) = self.cache.get(country) {
+            debug!(country, "cache hit");
+            self.record(Event::CacheHit);
+            self.update_stats(|stats| {
+                stats.hits += 1;
+                stats.last_source = Some(Origin::Cache);
+            });
+            return Ok(Some(info.rounded(self.precision)));
+        }
+        Ok(self.get_data()?.remove(country))
+    }
+
+    pub fn dataset(&self) -> Result<Dataset> {
+        let (data, origin) = self.get_data_with_origin()?;
+        let dataset = Dataset::new(data).with_origin(origin);
+        // A missing or unreadable record still credits the article
+        let provenance = self.provenance().ok().flatten();
+        Ok(dataset.with_attribution(Attribution::wikipedia(provenance.as_ref())))
+    }
+
+    fn report(&self, phase: Phase, fraction: f32) {
+        if let Some(progress) = &self.progress {
+            progress(phase, fraction);
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::new()
+    }
+}
+
+impl ClientBuilder {
+    pub fn source(mut self, source: impl DataSource + 'static) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Downloads the Wikipedia article through `transport`, replacing any `source` set before.
+    #[cfg(feature = "blocking")]
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.source = None;
+        self.wikipedia.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Reads the article's table `selector` picks, see `WikipediaSource::table`. Replaces any
+    /// `source` set before.
+    #[cfg(feature = "blocking")]
+    pub fn table(mut self, selector: TableSelector) -> Self {
+        self.source = None;
+        self.wikipedia = self.wikipedia.table(selector);
+        self
+    }
+
+    /// Checks robots.txt before downloading the article, see `WikipediaSource::respect_robots`.
+    /// Replaces any `source` set before.
+    #[cfg(feature = "blocking")]
+    pub fn respect_robots(mut self) -> Self {
+        self.source = None;
+        self.wikipedia = self.wikipedia.respect_robots();
+        self
+    }
+
+    pub fn cache(mut self, cache: impl CacheStore + 'static) -> Self {
+        self.cache = Some(Box::new(cache));
+        self
+    }
+
+    /// Archives every fetch in `snapshots` and answers `Client::get_data_for_year` from it,
+    /// instead of the cache's own, see `CacheStore::snapshots`. An in-memory client archives
+    /// nothing unless set.
+    pub fn snapshots(mut self, snapshots: SnapshotCache) -> Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+
+    /// Called during a cold refresh, a warm cache hit reports nothing.
+    pub fn on_progress(mut self, progress: impl Fn(Phase, f32) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Rounding applied to every returned value, the cache always keeps the source precision.
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Fail with `Error::StaleOnly` when the cache is empty and the fetch fails, instead of answering from the bundled defaults.
+    /// Same as `fallback_policy(FallbackPolicy::PreferStaleCache)`.
+    pub fn no_defaults(self) -> Self {
+        self.fallback_policy(FallbackPolicy::PreferStaleCache)
+    }
+
+    /// What to do once a fetch fails, `FallbackPolicy::AllowDefaults` unless set. Any policy but
+    /// that one drops `Origin::BundledFile` and `Origin::Embedded` from the fallback chain.
+    pub fn fallback_policy(mut self, policy: FallbackPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// No filesystem IO at all: a `MemoryCache` unless another cache is set, and the defaults
+    /// embedded in the binary rather than `default_expectancy.json`, for serverless and read-only deployments.
+    /// Drops `Origin::BundledFile` from the fallback chain.
+    pub fn in_memory(mut self) -> Self {
+        self.in_memory = true;
+        self
+    }
+
+    /// Leaves dependencies and other territories out of every answer, "Common" averaging
+    /// the countries only. The cache still holds every entry.
+    pub fn countries_only(mut self) -> Self {
+        self.countries_only = true;
+        self
+    }
+
+    /// Answers with territories alongside countries, the default.
+    pub fn include_territories(mut self) -> Self {
+        self.countries_only = false;
+        self
+    }
+
+    /// Downloads the signed `default_expectancy.json` of the latest GitHub release when the
+    /// fetch fails and there is no trusted local one, rather than failing.
+    #[cfg(feature = "blocking")]
+    pub fn release_defaults(self) -> Self {
+        self.defaults_mirror(RELEASES_URL)
+    }
+
+    /// Same as `release_defaults` from a mirror of the release assets, serving
+    /// `<url>/default_expectancy.json` and `<url>/default_expectancy.json.sig`.
+    #[cfg(feature = "blocking")]
+    pub fn defaults_mirror(mut self, url: impl Into<String>) -> Self {
+        self.defaults_url = Some(url.into());
+        self
+    }
+
+    /// Layers to try in order, `DEFAULT_FALLBACK` unless set. `[Origin::Network, Origin::Cache]`
+    /// for instance prefers fresh data and answers from the cache offline, never from the defaults.
+    pub fn fallback(mut self, layers: impl IntoIterator<Item = Origin>) -> Self {
+        self.fallback = Some(layers.into_iter().collect());
+        self
+    }
+
+    /// Runs `transform` on every fetch before the data is cached, after the ones added before.
+    /// The bundled defaults are left as they are.
+    pub fn transform(mut self, transform: impl Transform + 'static) -> Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    pub fn build(self) -> Client {
+        let mut fallback = self.fallback.unwrap_or_else(|| DEFAULT_FALLBACK.to_vec());
+        fallback.retain(|layer| {
+            !(self.policy != FallbackPolicy::AllowDefaults && layer.is_default()
+                || (self.in_memory || cfg!(not(feature = "defaults-file")))
+                    && *layer == Origin::BundledFile)
+        });
+        #[cfg(feature = "blocking")]
+        let wikipedia = self.source.is_none().then(|| self.wikipedia.clone());
+        let cache = self.cache.unwrap_or_else(|| {
+            if self.in_memory {
+                Box::new(MemoryCache::default())
+            } else {
+                Box::new(FileCache::default())
+            }
+        });
+        let snapshots = match self.snapshots {
+            Some(snapshots) => Some(snapshots),
+            None if self.in_memory => None,
+            None => cache.snapshots(),
+        };
+        let provenance_path = (!self.in_memory).then(|| cache.provenance_path()).flatten();
+        Client {
+            #[cfg(feature = "blocking")]
+            source: self.source.unwrap_or_else(|| Box::new(self.wikipedia)),
+            #[cfg(feature = "blocking")]
+            wikipedia,
+            #[cfg(not(feature = "blocking"))]
+            source: self.source.unwrap_or_else(default_source),
+            cache,
+            snapshots,
+            provenance_path,
+            progress: self.progress,
+            precision: self.precision,
+            policy: self.policy,
+            in_memory: self.in_memory,
+            countries_only: self.countries_only,
+            transforms: self.transforms,
+            #[cfg(feature = "blocking")]
+            defaults_url: self.defaults_url,
+            fallback,
+            stats: Mutex::default(),
+            provenance: Mutex::default(),
+        }
+    }
+}
+
+#[cfg(not(feature = "blocking"))]
+fn default_source() -> Box<dyn DataSource> {
+    Box::new(OfflineSource)
+}
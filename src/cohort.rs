@@ -0,0 +1,211 @@
+use crate::countries::country_by_iso3;
+use crate::gompertz::{GompertzMakeham, DEFAULT_BETA, DEFAULT_LAMBDA};
+use crate::limits::{check_length, Limited, MAX_ARCHIVE_BYTES, MAX_DECOMPRESSED_BYTES};
+use crate::{get_tmp_dir, read_tmp_file, write_tmp_file, CountryInfo, Precision, Sex};
+use anyhow::{anyhow, Result};
+use flate2::read::GzDecoder;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+
+// UN World Population Prospects, medium variant (estimates and projections up to 2100)
+const WPP_URL: &str = "https://population.un.org/wpp/assets/Excel%20Files/1_Indicator%20(Standard)/CSV_FILES/WPP2024_Demographic_Indicators_Medium.csv.gz";
+const MAX_AGE: f64 = 130.0;
+const STEP: f64 = 0.25;
+
+type PeriodSeries = BTreeMap<u32, CountryInfo>;
+
+pub fn get_cohort_data(birth_year: u32) -> Result<HashMap<String, CountryInfo>> {
+    get_cohort_data_with(birth_year, Precision::default())
+}
+
+pub fn get_cohort_data_with(
+    birth_year: u32,
+    precision: Precision,
+) -> Result<HashMap<String, CountryInfo>> {
+    // Renamed from `.tmp_cohort_<year>_expectancy.json`, keyed by UN location name
+    let path = get_tmp_dir().join(format!(".tmp_cohort_{}.json", birth_year));
+    let data = if let Some(data) = read_tmp_file(&path)? {
+        data
+    } else {
+        let data = fetch_cohort(birth_year)?;
+        write_tmp_file(&path, &data)?;
+        data
+    };
+
+    Ok(data
+        .into_iter()
+        .map(|(location, info)| (location, info.rounded(precision)))
+        .collect())
+}
+
+/// Cohort life expectancy at birth for people born in `birth_year`, unrounded. Keyed like
+/// `get_data`, locations missing from the bundled metadata keeping their UN name; regions and
+/// other UN aggregates are left out.
+pub fn fetch_cohort(birth_year: u32) -> Result<HashMap<String, CountryInfo>> {
+    let projections = fetch_period_projections()?;
+    let table = AlphaTable::new();
+    let mut result = HashMap::new();
+
+    for (location, series) in projections {
+        let expectancy = |sex| cohort_expectancy(&table, &series, sex, birth_year);
+        if let (Some(all), Some(male), Some(female)) = (
+            expectancy(Sex::All),
+            expectancy(Sex::Male),
+            expectancy(Sex::Female),
+        ) {
+            result.insert(location, CountryInfo { all, male, female });
+        }
+    }
+
+    if result.is_empty() {
+        return Err(anyhow!("No cohort data available for {}", birth_year));
+    }
+    Ok(result)
+}
+
+fn fetch_period_projections() -> Result<HashMap<String, PeriodSeries>> {
+    let resp = reqwest::blocking::get(WPP_URL)?.error_for_status()?;
+    check_length(resp.content_length(), MAX_ARCHIVE_BYTES, "The WPP archive")?;
+    let archive = Limited::new(resp, MAX_ARCHIVE_BYTES, "The WPP archive");
+    read_period_projections(Limited::new(
+        GzDecoder::new(archive),
+        MAX_DECOMPRESSED_BYTES,
+        "The decompressed WPP data",
+    ))
+}
+
+fn read_period_projections(csv: impl Read) -> Result<HashMap<String, PeriodSeries>> {
+    let mut reader = csv::Reader::from_reader(csv);
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header == name)
+            .ok_or_else(|| anyhow!("Missing column {} in WPP data", name))
+    };
+    let (location, iso3, time) = (column("Location")?, column("ISO3_code")?, column("Time")?);
+    let (all, male, female) = (column("LEx")?, column("LExMale")?, column("LExFemale")?);
+
+    let mut result: HashMap<String, PeriodSeries> = HashMap::new();
+    for record in reader.records() {
+        let record = record?;
+        let value = |index: usize| record.get(index).and_then(|v| v.trim().parse::<f64>().ok());
+        let year = record.get(time).and_then(|v| v.parse::<u32>().ok());
+        // Aggregates (World, regions, income groups) have no ISO code
+        let Some(iso3) = record.get(iso3).filter(|code| !code.trim().is_empty()) else {
+            continue;
+        };
+        let name = match country_by_iso3(iso3.trim()) {
+            Some(name) => name.to_string(),
+            None => record.get(location).unwrap_or_default().to_string(),
+        };
+        if let (Some(year), Some(all), Some(male), Some(female)) =
+            (year, value(all), value(male), value(female))
+        {
+            result
+                .entry(name)
+                .or_default()
+                .insert(year, CountryInfo { all, male, female });
+        }
+    }
+
+    Ok(result)
+}
+
+// Translates projected period mortality into the mortality a cohort experiences
+// as it ages: at age x it is subject to the Gompertz hazard fitted to year birth_year + x.
+fn cohort_expectancy(
+    table: &AlphaTable,
+    series: &PeriodSeries,
+    sex: Sex,
+    birth_year: u32,
+) -> Option<f64> {
+    series.get(&birth_year)?;
+    let (&last_year, _) = series.last_key_value()?;
+
+    let mut models: HashMap<u32, GompertzMakeham> = HashMap::new();
+    let mut cumulative_hazard = 0.0_f64;
+    let mut expectancy = 0.0;
+    let steps = (MAX_AGE / STEP) as usize;
+    for i in 0..steps {
+        let age = i as f64 * STEP;
+        let year = (birth_year + age as u32).min(last_year);
+        let model = match models.get(&year) {
+            Some(model) => *model,
+            None => {
+                let model = table.fit(series.get(&year)?.get(sex))?;
+                models.insert(year, model);
+                model
+            }
+        };
+        let survival = (-cumulative_hazard).exp();
+        cumulative_hazard += model.hazard(age + STEP / 2.0) * STEP;
+        expectancy += (survival + (-cumulative_hazard).exp()) / 2.0 * STEP;
+    }
+
+    Some(expectancy)
+}
+
+// Precomputed e0 for a grid of ln(alpha), so fitting thousands of yearly
+// models is a lookup instead of a bisection each time
+struct AlphaTable {
+    points: Vec<(f64, f64)>,
+}
+
+impl AlphaTable {
+    fn new() -> Self {
+        let points = (0..=2000)
+            .map(|i| {
+                let ln_alpha = -30.0 + i as f64 * 0.015;
+                let model = GompertzMakeham {
+                    alpha: ln_alpha.exp(),
+                    beta: DEFAULT_BETA,
+                    lambda: DEFAULT_LAMBDA,
+                };
+                (model.life_expectancy(), ln_alpha)
+            })
+            .collect();
+
+        AlphaTable { points }
+    }
+
+    fn fit(&self, life_expectancy: f64) -> Option<GompertzMakeham> {
+        // e0 decreases as ln(alpha) grows
+        let index = self
+            .points
+            .iter()
+            .position(|(e0, _)| *e0 <= life_expectancy)?;
+        let ln_alpha = if index == 0 {
+            self.points[0].1
+        } else {
+            let (e_high, a_low) = self.points[index - 1];
+            let (e_low, a_high) = self.points[index];
+            a_low + (a_high - a_low) * (e_high - life_expectancy) / (e_high - e_low)
+        };
+
+        Some(GompertzMakeham {
+            alpha: ln_alpha.exp(),
+            beta: DEFAULT_BETA,
+            lambda: DEFAULT_LAMBDA,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_locations_by_iso3_without_aggregates() {
+        let csv = "Location,ISO3_code,Time,LEx,LExMale,LExFemale\n\
+                   World,,2024,73.3,70.9,75.8\n\
+                   Türkiye,TUR,2024,77.4,74.9,80.1\n\
+                   Viet Nam,VNM,2024,74.6,70.1,79.2\n\
+                   Kosovo (under UNSC res. 1244),XKX,2024,79.0,76.6,81.8\n\
+                   Channel Islands,CHI,2024,83.1,81.2,85.0\n";
+        let projections = read_period_projections(csv.as_bytes()).unwrap();
+        let mut names: Vec<&str> = projections.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["Channel Islands", "Kosovo", "Turkey", "Vietnam"]);
+    }
+}
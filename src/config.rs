@@ -0,0 +1,51 @@
+//! `config.toml` in the per-user config directory (`~/.config/lifespan/config.toml` on Linux),
+//! or the file `--config` names. Command-line flags take precedence over it.
+
+use crate::i18n::t;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Same as `--script`, relative to the directory of the file.
+    pub script: Option<PathBuf>,
+}
+
+fn default_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("lifespan").join("config.toml"))
+}
+
+/// The file at `path`, else the one at `default_path` when there is one.
+pub fn load(path: Option<&Path>) -> Result<Config> {
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Config::default()),
+        },
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if !required && err.kind() == io::ErrorKind::NotFound => {
+            return Ok(Config::default())
+        }
+        Err(err) => {
+            return Err(anyhow!(t!(
+                "config.invalid",
+                path = path.display(),
+                error = err
+            )))
+        }
+    };
+    let mut config: Config = toml::from_str(&text)
+        .map_err(|err| anyhow!(t!("config.invalid", path = path.display(), error = err)))?;
+    if let (Some(script), Some(dir)) = (&mut config.script, path.parent()) {
+        *script = dir.join(&*script);
+    }
+
+    Ok(config)
+}
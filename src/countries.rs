@@ -0,0 +1,191 @@
+use crate::{calculate_common, CountryInfo, Precision, COMMON};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+const COUNTRIES_JSON: &str = include_str!("../countries.json");
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WhoRegion {
+    Africa,
+    Americas,
+    SouthEastAsia,
+    Europe,
+    EasternMediterranean,
+    WesternPacific,
+}
+
+impl FromStr for WhoRegion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "africa" => Ok(WhoRegion::Africa),
+            "americas" => Ok(WhoRegion::Americas),
+            "southeastasia" => Ok(WhoRegion::SouthEastAsia),
+            "europe" => Ok(WhoRegion::Europe),
+            "easternmediterranean" => Ok(WhoRegion::EasternMediterranean),
+            "westernpacific" => Ok(WhoRegion::WesternPacific),
+            _ => Err(anyhow!("Unknown WHO region: {}", s)),
+        }
+    }
+}
+
+/// World Bank income classification.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IncomeGroup {
+    Low,
+    LowerMiddle,
+    UpperMiddle,
+    High,
+}
+
+/// What an entry of the data stands for.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EntityKind {
+    /// A sovereign state, partially recognized ones included.
+    #[default]
+    Country,
+    /// A dependency, a special administrative region or one of the Palestinian territories.
+    Territory,
+    /// The world or a group of countries, "Common" included.
+    Region,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CountryMetadata {
+    pub iso2: String,
+    pub iso3: String,
+    // Territories are not WHO members, and a few have no World Bank classification
+    pub who_region: Option<WhoRegion>,
+    pub income_group: Option<IncomeGroup>,
+    /// `Country` or `Territory`, aggregates have no metadata.
+    #[serde(default)]
+    pub kind: EntityKind,
+}
+
+/// Bundled metadata, keyed by the same names as the expectancy data.
+pub fn countries() -> &'static HashMap<String, CountryMetadata> {
+    static COUNTRIES: OnceLock<HashMap<String, CountryMetadata>> = OnceLock::new();
+    COUNTRIES.get_or_init(|| {
+        serde_json::from_str(COUNTRIES_JSON).expect("bundled countries.json is valid")
+    })
+}
+
+pub fn country_metadata(country: &str) -> Option<&'static CountryMetadata> {
+    countries().get(country)
+}
+
+/// Dataset name for an ISO 3166-1 alpha-3 code, the first one alphabetically
+/// when several entries share a code (Gaza Strip and West Bank).
+pub fn country_by_iso3(iso3: &str) -> Option<&'static str> {
+    countries()
+        .iter()
+        .filter(|(_, metadata)| metadata.iso3.eq_ignore_ascii_case(iso3))
+        .map(|(name, _)| name.as_str())
+        .min()
+}
+
+/// Dataset name for an ISO 3166-1 alpha-2 or alpha-3 code.
+pub fn country_by_code(code: &str) -> Option<&'static str> {
+    match code.len() {
+        2 => countries()
+            .iter()
+            .filter(|(_, metadata)| metadata.iso2.eq_ignore_ascii_case(code))
+            .map(|(name, _)| name.as_str())
+            .min(),
+        3 => country_by_iso3(code),
+        _ => None,
+    }
+}
+
+pub fn who_region(country: &str) -> Option<WhoRegion> {
+    country_metadata(country)?.who_region
+}
+
+pub fn income_group(country: &str) -> Option<IncomeGroup> {
+    country_metadata(country)?.income_group
+}
+
+/// Rows of the world, WHO regions and UN continents some tables list among the countries.
+pub const AGGREGATES: &[&str] = &[
+    "World",
+    "Africa",
+    "Americas",
+    "Asia",
+    "Europe",
+    "Oceania",
+    "Eastern Mediterranean",
+    "South-East Asia",
+    "Western Pacific",
+    "Latin America and the Caribbean",
+    "Northern America",
+    "European Union",
+];
+
+pub fn is_aggregate(name: &str) -> bool {
+    AGGREGATES
+        .iter()
+        .any(|aggregate| aggregate.eq_ignore_ascii_case(name))
+}
+
+/// `None` for names that are neither in the bundled metadata nor an aggregate.
+pub fn entity_kind(name: &str) -> Option<EntityKind> {
+    if name == COMMON || is_aggregate(name) {
+        return Some(EntityKind::Region);
+    }
+    Some(country_metadata(name)?.kind)
+}
+
+pub fn is_territory(country: &str) -> bool {
+    entity_kind(country) == Some(EntityKind::Territory)
+}
+
+/// `content` without the entries `is_territory` flags, "Common" averaging the rest when it was there.
+pub fn countries_only(mut content: HashMap<String, CountryInfo>) -> HashMap<String, CountryInfo> {
+    content.retain(|name, _| !is_territory(name));
+    if content.remove(COMMON).is_some() {
+        content.insert(String::from(COMMON), calculate_common(&content));
+    }
+    content
+}
+
+pub fn average_by_who_region(
+    content: &HashMap<String, CountryInfo>,
+) -> HashMap<WhoRegion, CountryInfo> {
+    average_by(content, |metadata| metadata.who_region)
+}
+
+pub fn average_by_income_group(
+    content: &HashMap<String, CountryInfo>,
+) -> HashMap<IncomeGroup, CountryInfo> {
+    average_by(content, |metadata| metadata.income_group)
+}
+
+/// Unweighted average of every entry whose metadata maps to a group, "Common" and unknown entries are skipped.
+pub fn average_by<K, F>(content: &HashMap<String, CountryInfo>, group: F) -> HashMap<K, CountryInfo>
+where
+    K: Eq + Hash,
+    F: Fn(&CountryMetadata) -> Option<K>,
+{
+    let mut groups: HashMap<K, HashMap<String, CountryInfo>> = HashMap::new();
+    for (name, info) in content {
+        if let Some(key) = country_metadata(name).and_then(&group) {
+            groups
+                .entry(key)
+                .or_default()
+                .insert(name.clone(), info.clone());
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, members)| {
+            let average = calculate_common(&members).rounded(Precision::default());
+            (key, average)
+        })
+        .collect()
+}
@@ -0,0 +1,94 @@
+use crate::countries::{countries, country_by_iso3};
+use crate::lookup::normalize;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+// One generated table entry per variant
+struct Row {
+    country: Country,
+    alpha2: &'static str,
+    alpha3: &'static str,
+    name: &'static str,
+    iso_name: &'static str,
+    official_name: Option<&'static str>,
+}
+
+include!(concat!(env!("OUT_DIR"), "/country.rs"));
+
+impl Country {
+    /// Every variant, in alpha-2 order.
+    pub fn all() -> impl Iterator<Item = Country> {
+        TABLE.iter().map(|row| row.country)
+    }
+
+    fn row(self) -> &'static Row {
+        &TABLE[self as usize]
+    }
+
+    pub fn alpha2(self) -> &'static str {
+        self.row().alpha2
+    }
+
+    pub fn alpha3(self) -> &'static str {
+        self.row().alpha3
+    }
+
+    /// Short English name, "Bolivia" rather than the ISO "Bolivia, Plurinational State of".
+    pub fn name(self) -> &'static str {
+        self.row().name
+    }
+
+    pub fn official_name(self) -> Option<&'static str> {
+        self.row().official_name
+    }
+
+    /// Key of the country in the expectancy data, `None` for territories the source doesn't list.
+    pub fn dataset_name(self) -> Option<&'static str> {
+        country_by_iso3(self.alpha3())
+    }
+}
+
+// Normalized alias -> country, built on the first parse
+fn aliases() -> &'static HashMap<String, Country> {
+    static ALIASES: OnceLock<HashMap<String, Country>> = OnceLock::new();
+    ALIASES.get_or_init(|| {
+        let mut aliases = HashMap::new();
+        for row in TABLE {
+            let names = [row.alpha2, row.alpha3, row.name, row.iso_name];
+            for alias in names.into_iter().chain(row.official_name) {
+                aliases.insert(normalize(alias), row.country);
+            }
+        }
+        // Dataset keys, "Czech Republic" or "Russia" where ISO says otherwise
+        let by_alpha3: HashMap<&str, Country> =
+            TABLE.iter().map(|row| (row.alpha3, row.country)).collect();
+        for (name, metadata) in countries() {
+            if let Some(&country) = by_alpha3.get(metadata.iso3.as_str()) {
+                aliases.entry(normalize(name)).or_insert(country);
+            }
+        }
+
+        aliases
+    })
+}
+
+impl FromStr for Country {
+    type Err = anyhow::Error;
+
+    /// Accepts alpha-2 and alpha-3 codes, the ISO, common and official names and the dataset key, leniently like `normalize`.
+    fn from_str(s: &str) -> Result<Self> {
+        aliases()
+            .get(&normalize(s))
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown country: {}", s))
+    }
+}
+
+impl fmt::Display for Country {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
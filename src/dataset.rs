@@ -0,0 +1,283 @@
+use crate::client::Origin;
+use crate::countries;
+use crate::export::Attribution;
+use crate::lookup::{normalize, CountryName};
+use crate::{get_data, CountryInfo, Precision, Sex, COMMON};
+use anyhow::{anyhow, Result};
+#[cfg(feature = "rayon")]
+use rayon::slice::ParallelSliceMut;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(transparent)]
+pub struct Dataset {
+    countries: HashMap<CountryName, CountryInfo>,
+    // Normalized name -> key, built on the first fuzzy lookup
+    #[serde(skip)]
+    index: OnceLock<HashMap<String, CountryName>>,
+    #[serde(skip)]
+    attribution: Attribution,
+    #[serde(skip)]
+    origin: Option<Origin>,
+}
+
+// Far more than any useful chart, few enough to stay a small allocation
+const MAX_BUCKETS: i64 = 10_000;
+
+/// Histogram bucket covering `[start, end)`, with a country count per sex.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Bucket {
+    pub start: f64,
+    pub end: f64,
+    pub all: usize,
+    pub male: usize,
+    pub female: usize,
+}
+
+/// Distribution of one sex's values over every entry, see `Dataset::summary`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Summary {
+    pub sex: Sex,
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64,
+    pub min: (String, f64),
+    pub max: (String, f64),
+}
+
+impl Dataset {
+    /// Keys are cleaned through `CountryName`, entries whose name it rejects are dropped.
+    pub fn new(countries: HashMap<String, CountryInfo>) -> Self {
+        Dataset {
+            countries: countries
+                .into_iter()
+                .filter_map(|(name, info)| match CountryName::new(&name) {
+                    Ok(name) => Some((name, info)),
+                    Err(err) => {
+                        tracing::warn!("dropping an entry: {:#}", err);
+                        None
+                    }
+                })
+                .collect(),
+            index: OnceLock::new(),
+            attribution: Attribution::default(),
+            origin: None,
+        }
+    }
+
+    /// Credit carried into every export, the Wikipedia article unless replaced.
+    pub fn with_attribution(mut self, attribution: Attribution) -> Self {
+        self.attribution = attribution;
+        self
+    }
+
+    pub fn attribution(&self) -> &Attribution {
+        &self.attribution
+    }
+
+    /// Layer of the client's fallback chain the data came from, kept through `rounded` and
+    /// `countries_only`.
+    pub fn with_origin(mut self, origin: Origin) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// `None` for a dataset not loaded through a `Client`.
+    pub fn origin(&self) -> Option<Origin> {
+        self.origin
+    }
+
+    pub fn load() -> Result<Self> {
+        Ok(Dataset::new(get_data()?))
+    }
+
+    pub fn countries(&self) -> &HashMap<CountryName, CountryInfo> {
+        &self.countries
+    }
+
+    pub fn into_countries(self) -> HashMap<CountryName, CountryInfo> {
+        self.countries
+    }
+
+    /// Every value rounded to `precision`, for datasets not loaded through a `Client`.
+    pub fn rounded(&self, precision: Precision) -> Dataset {
+        Dataset {
+            countries: self
+                .countries
+                .iter()
+                .map(|(name, info)| (name.clone(), info.rounded(precision)))
+                .collect(),
+            ..Dataset::default()
+        }
+        .with_metadata_of(self)
+    }
+
+    /// Without dependencies and other territories, see `countries::is_territory`.
+    pub fn countries_only(&self) -> Dataset {
+        let countries = self
+            .countries
+            .iter()
+            .map(|(name, info)| (name.to_string(), info.clone()))
+            .collect();
+        Dataset::new(countries::countries_only(countries)).with_metadata_of(self)
+    }
+
+    fn with_metadata_of(self, other: &Dataset) -> Self {
+        Dataset {
+            attribution: other.attribution.clone(),
+            origin: other.origin,
+            ..self
+        }
+    }
+
+    pub fn insert(&mut self, country: CountryName, info: CountryInfo) -> Option<CountryInfo> {
+        self.index = OnceLock::new();
+        self.countries.insert(country, info)
+    }
+
+    /// Looks `country` up exactly, then ignoring case, diacritics, apostrophe style and extra whitespace.
+    pub fn get(&self, country: &str) -> Option<&CountryInfo> {
+        self.get_key_value(country).map(|(_, info)| info)
+    }
+
+    pub fn get_key_value(&self, country: &str) -> Option<(&CountryName, &CountryInfo)> {
+        if let Some(entry) = self.countries.get_key_value(country) {
+            return Some(entry);
+        }
+        let index = self.index.get_or_init(|| {
+            self.countries
+                .keys()
+                .map(|name| (normalize(name), name.clone()))
+                .collect()
+        });
+
+        self.countries
+            .get_key_value(index.get(&normalize(country))?)
+    }
+
+    /// Every entry except the computed "Common" average.
+    pub fn entries(&self) -> impl Iterator<Item = (&CountryName, &CountryInfo)> {
+        self.countries
+            .iter()
+            .filter(|(name, _)| name.as_str() != COMMON)
+    }
+
+    /// Entries sorted from the highest to the lowest value.
+    pub fn ranked(&self, sex: Sex) -> Vec<(&CountryName, &CountryInfo)> {
+        let mut entries: Vec<_> = self.entries().collect();
+        let order = |(_, a): &(&CountryName, &CountryInfo),
+                     (_, b): &(&CountryName, &CountryInfo)| {
+            b.get(sex).total_cmp(&a.get(sex))
+        };
+        #[cfg(feature = "rayon")]
+        entries.par_sort_by(order);
+        #[cfg(not(feature = "rayon"))]
+        entries.sort_by(order);
+        entries
+    }
+
+    /// Share of entries (0–100) with a lower value than `country`, ties counting half.
+    pub fn percentile_of(&self, country: &str, sex: Sex) -> Option<f64> {
+        let value = self.get(country)?.get(sex);
+        let (mut below, mut equal, mut total) = (0, 0, 0);
+        for (_, info) in self.entries() {
+            let other = info.get(sex);
+            if other < value {
+                below += 1;
+            } else if other == value {
+                equal += 1;
+            }
+            total += 1;
+        }
+        if total == 0 {
+            return None;
+        }
+
+        Some((below as f64 + equal as f64 / 2.0) / total as f64 * 100.0)
+    }
+
+    /// `None` when the dataset has no entries.
+    pub fn summary(&self, sex: Sex) -> Option<Summary> {
+        let ranked = self.ranked(sex);
+        let (first, last) = (ranked.first()?, ranked.last()?);
+        let values: Vec<f64> = ranked.iter().rev().map(|(_, info)| info.get(sex)).collect();
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let median = if count.is_multiple_of(2) {
+            (values[count / 2 - 1] + values[count / 2]) / 2.0
+        } else {
+            values[count / 2]
+        };
+        let variance = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / count as f64;
+
+        Some(Summary {
+            sex,
+            count,
+            mean,
+            median,
+            std_dev: variance.sqrt(),
+            min: (last.0.to_string(), last.1.get(sex)),
+            max: (first.0.to_string(), first.1.get(sex)),
+        })
+    }
+
+    /// Buckets aligned on multiples of `bucket_width`, spanning the lowest to the highest value of any sex.
+    /// Errors when the width is not a positive number or would make more than 10 000 buckets.
+    pub fn histogram(&self, bucket_width: f64) -> Result<Vec<Bucket>> {
+        if !bucket_width.is_finite() || bucket_width <= 0.0 {
+            return Err(anyhow!("{} is not a bucket width", bucket_width));
+        }
+        let index = |value: f64| (value / bucket_width).floor() as i64;
+        let indices: Vec<i64> = self
+            .entries()
+            .flat_map(|(_, info)| [info.all, info.male, info.female])
+            .map(index)
+            .collect();
+        let (Some(&first), Some(&last)) = (indices.iter().min(), indices.iter().max()) else {
+            return Ok(Vec::new());
+        };
+        // `as` saturates, so an index at either end of i64 stands for one out of its range
+        let saturated = first == i64::MIN || last == i64::MAX;
+        if saturated
+            || last
+                .checked_sub(first)
+                .is_none_or(|span| span >= MAX_BUCKETS)
+        {
+            return Err(anyhow!(
+                "A width of {} makes more than {} buckets",
+                bucket_width,
+                MAX_BUCKETS
+            ));
+        }
+
+        let mut buckets: Vec<Bucket> = (first..=last)
+            .map(|i| Bucket {
+                start: i as f64 * bucket_width,
+                end: (i + 1) as f64 * bucket_width,
+                all: 0,
+                male: 0,
+                female: 0,
+            })
+            .collect();
+        for (_, info) in self.entries() {
+            buckets[(index(info.all) - first) as usize].all += 1;
+            buckets[(index(info.male) - first) as usize].male += 1;
+            buckets[(index(info.female) - first) as usize].female += 1;
+        }
+
+        Ok(buckets)
+    }
+}
+
+impl From<HashMap<String, CountryInfo>> for Dataset {
+    fn from(countries: HashMap<String, CountryInfo>) -> Self {
+        Dataset::new(countries)
+    }
+}
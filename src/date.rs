@@ -0,0 +1,80 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// (year, month, day) of a day count since 1970-01-01, Howard Hinnant's algorithm.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month as u32, day as u32)
+}
+
+/// Inverse of `civil_from_days`.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// UTC calendar date of `time`.
+pub(crate) fn civil_date(time: SystemTime) -> (i64, u32, u32) {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    civil_from_days((secs / SECS_PER_DAY) as i64)
+}
+
+/// Midnight UTC at the start of the date, clamped to the epoch.
+pub(crate) fn to_system_time(year: i64, month: u32, day: u32) -> SystemTime {
+    let days = days_from_civil(year, month, day).max(0) as u64;
+    UNIX_EPOCH + Duration::from_secs(days * SECS_PER_DAY)
+}
+
+/// `YYYY-MM-DD` of `time` in UTC.
+pub(crate) fn iso_date(time: SystemTime) -> String {
+    let (year, month, day) = civil_date(time);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// An IMF-fixdate such as `Sun, 06 Nov 1994 08:49:37 GMT`, the form HTTP headers use.
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn parse_http_date(text: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let (_, rest) = text.trim().split_once(", ")?;
+    let mut parts = rest.split(' ');
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = MONTHS.iter().position(|name| Some(*name) == parts.next())? as u32 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut clock = parts
+        .next()?
+        .split(':')
+        .map(|part| part.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (clock.next()??, clock.next()??, clock.next()??);
+    if parts.next() != Some("GMT") || !(1..=31).contains(&day) || hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    let midnight = to_system_time(year, month, day);
+    Some(midnight + Duration::from_secs(hours * 3600 + minutes * 60 + seconds.min(60)))
+}
@@ -0,0 +1,62 @@
+//! Exact decimal values, enabled by the `decimal` feature, for pipelines that can't
+//! accept binary floating point rounding.
+
+use crate::{CountryInfo, Dataset, Sex};
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalInfo {
+    pub all: Decimal,
+    pub male: Decimal,
+    pub female: Decimal,
+}
+
+impl DecimalInfo {
+    pub fn get(&self, sex: Sex) -> Decimal {
+        match sex {
+            Sex::All => self.all,
+            Sex::Male => self.male,
+            Sex::Female => self.female,
+        }
+    }
+}
+
+impl TryFrom<&CountryInfo> for DecimalInfo {
+    type Error = anyhow::Error;
+
+    fn try_from(info: &CountryInfo) -> Result<Self> {
+        Ok(DecimalInfo {
+            all: to_decimal(info.all)?,
+            male: to_decimal(info.male)?,
+            female: to_decimal(info.female)?,
+        })
+    }
+}
+
+// Goes through the shortest round-trip representation, so 81.3 becomes exactly 81.3
+// rather than the binary 81.2999…
+fn to_decimal(value: f64) -> Result<Decimal> {
+    if !value.is_finite() {
+        return Err(anyhow!("{} has no decimal representation", value));
+    }
+    Ok(Decimal::from_str(&value.to_string())?)
+}
+
+pub fn decimal_values<K: ToString>(
+    content: &HashMap<K, CountryInfo>,
+) -> Result<HashMap<String, DecimalInfo>> {
+    content
+        .iter()
+        .map(|(name, info)| Ok((name.to_string(), DecimalInfo::try_from(info)?)))
+        .collect()
+}
+
+impl Dataset {
+    pub fn to_decimal(&self) -> Result<HashMap<String, DecimalInfo>> {
+        decimal_values(self.countries())
+    }
+}
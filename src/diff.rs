@@ -0,0 +1,86 @@
+use crate::{CountryInfo, COMMON};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What changed between two snapshots, each list sorted by name. "Common" is left out.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DataDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<Changed>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Changed {
+    pub country: String,
+    pub before: CountryInfo,
+    pub after: CountryInfo,
+}
+
+impl DataDiff {
+    pub fn between(
+        before: &HashMap<String, CountryInfo>,
+        after: &HashMap<String, CountryInfo>,
+    ) -> Self {
+        let mut diff = DataDiff::default();
+        for (country, new) in after {
+            if country == COMMON {
+                continue;
+            }
+            match before.get(country) {
+                None => diff.added.push(country.clone()),
+                Some(old)
+                    if old.all != new.all || old.male != new.male || old.female != new.female =>
+                {
+                    diff.changed.push(Changed {
+                        country: country.clone(),
+                        before: old.clone(),
+                        after: new.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = before
+            .keys()
+            .filter(|country| *country != COMMON && !after.contains_key(*country))
+            .cloned()
+            .collect();
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort_by(|a, b| a.country.cmp(&b.country));
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// One line, e.g. "12 changed, 1 added, 0 removed; largest change: Japan +0.40".
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} changed, {} added, {} removed",
+            self.changed.len(),
+            self.added.len(),
+            self.removed.len()
+        );
+        let largest = self
+            .changed
+            .iter()
+            .max_by(|a, b| delta(a).abs().total_cmp(&delta(b).abs()));
+        if let Some(largest) = largest {
+            summary.push_str(&format!(
+                "; largest change: {} {:+.2}",
+                largest.country,
+                delta(largest)
+            ));
+        }
+
+        summary
+    }
+}
+
+fn delta(changed: &Changed) -> f64 {
+    changed.after.vs(&changed.before).all
+}
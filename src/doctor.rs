@@ -0,0 +1,153 @@
+use crate::cache::{CacheStore, FileCache};
+use crate::html::extract_tables;
+use crate::{get_tmp_dir, parse_countries, FETCH_URL};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+// Fewer parsed entries than this means the table layout changed under the parser
+const MIN_ENTRIES: usize = 150;
+// The source table is updated about once a year
+const MAX_CACHE_AGE: Duration = Duration::from_secs(400 * 24 * 3600);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warning,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or failure.
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &str, detail: String) -> Self {
+        Check {
+            name: name.to_string(),
+            status: Status::Ok,
+            detail,
+            fix: None,
+        }
+    }
+
+    fn problem(name: &str, status: Status, detail: String, fix: &str) -> Self {
+        Check {
+            name: name.to_string(),
+            status,
+            detail,
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+/// Checks the source page, the cache directory and the cache, in that order.
+pub fn run() -> Vec<Check> {
+    let mut checks = source_checks();
+    checks.push(cache_dir_check());
+    checks.push(cache_check());
+    checks
+}
+
+fn source_checks() -> Vec<Check> {
+    let resp = match reqwest::blocking::get(FETCH_URL) {
+        Ok(resp) => resp,
+        Err(err) => {
+            return vec![Check::problem(
+            "network",
+            Status::Failed,
+            format!("{} is unreachable: {}", FETCH_URL, err),
+            "Check the connection and any HTTPS_PROXY setting, the bundled data is used meanwhile",
+        )]
+        }
+    };
+    let status = resp.status();
+    if !status.is_success() {
+        return vec![Check::problem(
+            "network",
+            Status::Failed,
+            format!("{} answered {}", FETCH_URL, status),
+            "Retry later, or check whether the article moved",
+        )];
+    }
+    let network = Check::ok("network", format!("{} answered {}", FETCH_URL, status));
+
+    let parsed = extract_tables(resp).and_then(|tables| parse_countries(&tables));
+    let parser = match parsed {
+        // "Common" is inserted next to the countries
+        Ok(data) if data.len() > MIN_ENTRIES => {
+            Check::ok("parser", format!("{} countries parsed", data.len() - 1))
+        }
+        Ok(data) => Check::problem(
+            "parser",
+            Status::Failed,
+            format!("only {} entries parsed", data.len().saturating_sub(1)),
+            "The table layout likely changed, keep using the cache and report it upstream",
+        ),
+        Err(err) => Check::problem(
+            "parser",
+            Status::Failed,
+            format!("parsing failed: {:#}", err),
+            "The table layout likely changed, keep using the cache and report it upstream",
+        ),
+    };
+
+    vec![network, parser]
+}
+
+fn cache_dir_check() -> Check {
+    let dir = get_tmp_dir();
+    let probe = dir.join(".doctor_probe");
+    let written = fs::create_dir_all(&dir).and_then(|_| fs::write(&probe, b"ok"));
+    let _ = fs::remove_file(&probe);
+    match written {
+        Ok(()) => Check::ok("cache dir", format!("{} is writable", dir.display())),
+        Err(err) => Check::problem(
+            "cache dir",
+            Status::Failed,
+            format!("{} is not writable: {}", dir.display(), err),
+            "Fix the directory's permissions, or point HOME somewhere writable",
+        ),
+    }
+}
+
+fn cache_check() -> Check {
+    let cache = FileCache::default();
+    let path = cache.path().display().to_string();
+    match (cache.load(), cache.modified()) {
+        (Ok(Some(data)), Ok(modified)) => {
+            let age = modified
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .unwrap_or_default();
+            let days = age.as_secs() / 86_400;
+            let detail = format!("{} entries in {}, {} days old", data.len(), path, days);
+            if age > MAX_CACHE_AGE {
+                Check::problem(
+                    "cache",
+                    Status::Warning,
+                    detail,
+                    "Delete the file or run `lifespan watch` to refresh",
+                )
+            } else {
+                Check::ok("cache", detail)
+            }
+        }
+        (Ok(None), _) => Check::problem(
+            "cache",
+            Status::Warning,
+            format!("no valid cache at {}", path),
+            "Any command fills it on the next run, a corrupt file is kept next to it as .corrupt",
+        ),
+        (Err(err), _) | (_, Err(err)) => Check::problem(
+            "cache",
+            Status::Failed,
+            format!("{} is unreadable: {:#}", path, err),
+            "Delete the file, it is rebuilt on the next run",
+        ),
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Failures callers may want to tell apart, carried inside `anyhow::Error`, see `error_kind`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A country name or code matched no entry.
+    UnknownCountry(String),
+    /// The page was fetched but its table could not be read.
+    Parse(String),
+    /// The fetch failed while the client is not allowed to answer from the bundled defaults.
+    StaleOnly,
+    /// The source answered 429 Too Many Requests, with how long it asked to wait when it said.
+    RateLimited { retry_after: Option<Duration> },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownCountry(country) => write!(f, "Unknown country: {}", country),
+            Error::Parse(reason) => write!(f, "Unparsable page: {}", reason),
+            Error::StaleOnly => write!(f, "Only the bundled defaults are available"),
+            Error::RateLimited {
+                retry_after: Some(wait),
+            } => write!(
+                f,
+                "Rate limited by the source, retry in {}s",
+                wait.as_secs()
+            ),
+            Error::RateLimited { retry_after: None } => write!(f, "Rate limited by the source"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Network,
+    Parse,
+    UnknownCountry,
+    StaleOnly,
+    RateLimited,
+    Other,
+}
+
+/// Classifies an error by the first `Error` in its chain, or as `Network` when the chain holds a request failure.
+pub fn error_kind(err: &anyhow::Error) -> ErrorKind {
+    // Context attached with `anyhow::Context` is only found through the outer error
+    let error = err
+        .downcast_ref::<Error>()
+        .or_else(|| err.chain().find_map(|cause| cause.downcast_ref::<Error>()));
+    if let Some(error) = error {
+        return match error {
+            Error::UnknownCountry(_) => ErrorKind::UnknownCountry,
+            Error::Parse(_) => ErrorKind::Parse,
+            Error::StaleOnly => ErrorKind::StaleOnly,
+            Error::RateLimited { .. } => ErrorKind::RateLimited,
+        };
+    }
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    if err.chain().any(|cause| cause.is::<reqwest::Error>()) {
+        return ErrorKind::Network;
+    }
+
+    ErrorKind::Other
+}
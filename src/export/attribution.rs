@@ -0,0 +1,107 @@
+use crate::provenance::Provenance;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+
+const ARTICLE_URL: &str = "https://en.wikipedia.org/wiki/List_of_countries_by_life_expectancy";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum License {
+    /// Wikipedia text and tables.
+    #[serde(rename = "CC BY-SA 4.0")]
+    CcBySa4,
+    /// WHO Global Health Observatory and Our World in Data figures.
+    #[serde(rename = "CC BY 4.0")]
+    CcBy4,
+}
+
+impl License {
+    pub fn name(self) -> &'static str {
+        match self {
+            License::CcBySa4 => "CC BY-SA 4.0",
+            License::CcBy4 => "CC BY 4.0",
+        }
+    }
+
+    pub fn url(self) -> &'static str {
+        match self {
+            License::CcBySa4 => "https://creativecommons.org/licenses/by-sa/4.0/",
+            License::CcBy4 => "https://creativecommons.org/licenses/by/4.0/",
+        }
+    }
+}
+
+/// Credit and license of the figures a dataset holds, embedded in every export.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Attribution {
+    /// Publisher and title of the work.
+    pub source: String,
+    pub url: String,
+    pub license: License,
+    /// Retrieval date, `YYYY-MM-DD`, when known.
+    pub retrieved: Option<String>,
+}
+
+impl Attribution {
+    /// The life expectancy article, linking the revision read when `provenance` knows it.
+    pub fn wikipedia(provenance: Option<&Provenance>) -> Self {
+        Attribution {
+            source: "Wikipedia, \"List of countries by life expectancy\"".to_string(),
+            url: provenance.map_or_else(|| ARTICLE_URL.to_string(), Provenance::permalink),
+            license: License::CcBySa4,
+            retrieved: provenance.map(Provenance::retrieved_on),
+        }
+    }
+
+    /// The yearly series of `history`.
+    pub fn who() -> Self {
+        Attribution {
+            source: "World Health Organization, Global Health Observatory".to_string(),
+            url: "https://www.who.int/data/gho".to_string(),
+            license: License::CcBy4,
+            retrieved: None,
+        }
+    }
+
+    /// The fields along with `text` and the license URL, as JSON exports embed them.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "source": self.source,
+            "url": self.url,
+            "license": self.license.name(),
+            "license_url": self.license.url(),
+            "retrieved": self.retrieved,
+            "text": self.text(),
+        })
+    }
+
+    /// Single line credit, `Wikipedia, "List of countries by life expectancy" (<url>), retrieved
+    /// 2026-10-14, CC BY-SA 4.0 (<license url>)` for instance.
+    pub fn text(&self) -> String {
+        let retrieved = self
+            .retrieved
+            .as_ref()
+            .map(|date| format!(", retrieved {}", date))
+            .unwrap_or_default();
+        format!(
+            "{} ({}){}, {} ({})",
+            self.source,
+            self.url,
+            retrieved,
+            self.license.name(),
+            self.license.url()
+        )
+    }
+}
+
+impl Default for Attribution {
+    fn default() -> Self {
+        Attribution::wikipedia(None)
+    }
+}
+
+impl fmt::Display for Attribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::export::Attribution;
+#[cfg(feature = "history")]
+use crate::history::Series;
+use crate::Dataset;
+use anyhow::Result;
+use duckdb::{params, Connection};
+#[cfg(feature = "history")]
+use std::collections::HashMap;
+use std::path::Path;
+
+impl Dataset {
+    /// Replaces the `life_expectancy` table of the DuckDB database at `path`, creating the file if needed.
+    /// The attribution is the table's comment.
+    pub fn to_duckdb(&self, path: &Path) -> Result<()> {
+        let connection = Connection::open(path)?;
+        write_snapshot(self, &connection)
+    }
+
+    /// Same as `to_duckdb`, also replacing a `life_expectancy_history` table with one row per country and year.
+    #[cfg(feature = "history")]
+    pub fn to_duckdb_with_history(
+        &self,
+        history: &HashMap<String, Series>,
+        path: &Path,
+    ) -> Result<()> {
+        let connection = Connection::open(path)?;
+        write_snapshot(self, &connection)?;
+
+        connection.execute_batch(
+            "CREATE OR REPLACE TABLE life_expectancy_history (
+                country VARCHAR NOT NULL,
+                year INTEGER NOT NULL,
+                \"all\" DOUBLE NOT NULL,
+                male DOUBLE NOT NULL,
+                female DOUBLE NOT NULL,
+                PRIMARY KEY (country, year)
+            );",
+        )?;
+        let mut appender = connection.appender("life_expectancy_history")?;
+        for (country, series) in history {
+            for (year, info) in series {
+                appender.append_row(params![country, year, info.all, info.male, info.female])?;
+            }
+        }
+        appender.flush()?;
+        comment(&connection, "life_expectancy_history", &Attribution::who())?;
+
+        Ok(())
+    }
+}
+
+fn write_snapshot(dataset: &Dataset, connection: &Connection) -> Result<()> {
+    // `all` is reserved
+    connection.execute_batch(
+        "CREATE OR REPLACE TABLE life_expectancy (
+            country VARCHAR PRIMARY KEY,
+            \"all\" DOUBLE NOT NULL,
+            male DOUBLE NOT NULL,
+            female DOUBLE NOT NULL
+        );",
+    )?;
+    let mut appender = connection.appender("life_expectancy")?;
+    for (country, info) in dataset.countries() {
+        appender.append_row(params![country.as_str(), info.all, info.male, info.female])?;
+    }
+    appender.flush()?;
+
+    comment(connection, "life_expectancy", dataset.attribution())
+}
+
+fn comment(connection: &Connection, table: &str, attribution: &Attribution) -> Result<()> {
+    connection.execute_batch(&format!(
+        "COMMENT ON TABLE {} IS '{}';",
+        table,
+        attribution.text().replace('\'', "''")
+    ))?;
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use crate::countries::country_metadata;
+use crate::lookup::CountryName;
+use crate::{CountryInfo, Dataset};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Property names used by common boundary datasets (Natural Earth, geoBoundaries, world-atlas…)
+const ISO_PROPERTIES: &[&str] = &[
+    "ISO_A3", "iso_a3", "ADM0_A3", "ISO3", "iso3", "ISO_A2", "iso_a2", "ISO2", "iso2",
+];
+
+impl Dataset {
+    /// Joins the dataset to a boundaries FeatureCollection on ISO 3166-1 codes, keeping matched features
+    /// and adding `name`, `all`, `male` and `female` to their properties. The collection carries
+    /// the attribution as a foreign member. No boundaries ship with the crate, Natural Earth's
+    /// `ne_110m_admin_0_countries.geojson` is a small public domain one.
+    pub fn to_geojson(&self, boundaries: &Value) -> Result<Value> {
+        let features = boundaries
+            .get("features")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("Boundaries are not a GeoJSON FeatureCollection"))?;
+
+        let mut by_code: HashMap<&str, (&CountryName, &CountryInfo)> = HashMap::new();
+        for (name, info) in self.entries() {
+            if let Some(metadata) = country_metadata(name) {
+                by_code.insert(&metadata.iso3, (name, info));
+                by_code.insert(&metadata.iso2, (name, info));
+            }
+        }
+
+        let joined: Vec<Value> = features
+            .iter()
+            .filter_map(|feature| {
+                let properties = feature.get("properties").and_then(Value::as_object);
+                let (name, info) = ISO_PROPERTIES
+                    .iter()
+                    .filter_map(|key| properties?.get(*key)?.as_str())
+                    .chain(feature.get("id").and_then(Value::as_str))
+                    .find_map(|code| by_code.get(code))?;
+
+                let mut properties = properties.cloned().unwrap_or_else(Map::new);
+                properties.insert("name".into(), json!(name));
+                properties.insert("all".into(), json!(info.all));
+                properties.insert("male".into(), json!(info.male));
+                properties.insert("female".into(), json!(info.female));
+
+                let mut feature = feature.clone();
+                feature["properties"] = Value::Object(properties);
+                Some(feature)
+            })
+            .collect();
+
+        Ok(json!({
+            "type": "FeatureCollection",
+            "features": joined,
+            "attribution": self.attribution().to_json(),
+        }))
+    }
+
+    pub fn to_geojson_file(&self, boundaries: &Path) -> Result<Value> {
+        let boundaries = serde_json::from_str::<Value>(&fs::read_to_string(boundaries)?)?;
+        self.to_geojson(&boundaries)
+    }
+}
@@ -0,0 +1,162 @@
+use crate::countries::country_metadata;
+use crate::lookup::CountryName;
+use crate::{CountryInfo, Dataset};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// Semver of the documents `tagged` writes. Minor releases only add fields or kinds, so
+/// readers should ignore keys they don't know, anything removed, renamed or retyped bumps
+/// the major, which is also part of each `$schema`.
+pub const FORMAT_VERSION: &str = "1.1.0";
+const FORMAT_MAJOR: &str = "1";
+
+/// Top-level shape of the JSON output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonLayout {
+    /// `{"Japan": {"all": …}, …}`, the same shape as the cache.
+    #[default]
+    Map,
+    /// `[{"country": "Japan", "all": …}, …]`, sorted by key.
+    Records,
+}
+
+/// What identifies each country.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyBy {
+    #[default]
+    Name,
+    /// ISO 3166-1 alpha-2, entries without a code are dropped.
+    Iso2,
+    /// ISO 3166-1 alpha-3, entries without a code are dropped.
+    Iso3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldCase {
+    #[default]
+    Snake,
+    Camel,
+    Pascal,
+}
+
+/// The defaults serialize exactly like `Dataset` does through serde.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct JsonOptions {
+    pub layout: JsonLayout,
+    pub key_by: KeyBy,
+    pub case: FieldCase,
+}
+
+/// `{"$schema": "urn:lifespan-crawler:<kind>:<major>", "version": FORMAT_VERSION, "data": …}`,
+/// the envelope around every JSON document the CLI writes.
+pub fn tagged(kind: &str, data: impl Serialize) -> Result<Value> {
+    Ok(json!({
+        "$schema": schema_id(kind),
+        "version": FORMAT_VERSION,
+        "data": serde_json::to_value(data)?,
+    }))
+}
+
+/// The `data` of a `tagged` document of `kind`, or `value` itself when it carries no `$schema`,
+/// as written before the format was versioned.
+pub fn untagged(value: Value, kind: &str) -> Result<Value> {
+    let Value::Object(mut document) = value else {
+        return Ok(value);
+    };
+    let Some(schema) = document.get("$schema") else {
+        return Ok(Value::Object(document));
+    };
+    if schema.as_str() != Some(schema_id(kind).as_str()) {
+        return Err(anyhow!(
+            "Expected a {} document version {}.x, found {}",
+            kind,
+            FORMAT_MAJOR,
+            schema
+        ));
+    }
+
+    document
+        .remove("data")
+        .ok_or_else(|| anyhow!("Tagged {} document without data", kind))
+}
+
+fn schema_id(kind: &str) -> String {
+    format!("urn:lifespan-crawler:{}:{}", kind, FORMAT_MAJOR)
+}
+
+impl Dataset {
+    /// `to_json_with` wrapped by `tagged` as a "dataset", with an `attribution` next to the data.
+    pub fn to_tagged_json(&self, options: &JsonOptions) -> Result<Value> {
+        let mut document = tagged("dataset", self.to_json_with(options))?;
+        document["attribution"] = self.attribution().to_json();
+        Ok(document)
+    }
+
+    pub fn to_json_with(&self, options: &JsonOptions) -> Value {
+        let field = |name: &str| convert_case(name, options.case);
+        let mut entries: Vec<(String, &CountryName, &CountryInfo)> = self
+            .countries()
+            .iter()
+            .filter_map(|(name, info)| {
+                let key = match options.key_by {
+                    KeyBy::Name => name.to_string(),
+                    KeyBy::Iso2 => country_metadata(name)?.iso2.clone(),
+                    KeyBy::Iso3 => country_metadata(name)?.iso3.clone(),
+                };
+                Some((key, name, info))
+            })
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let values = |info: &CountryInfo| {
+            let mut values = Map::new();
+            values.insert(field("all"), json!(info.all));
+            values.insert(field("male"), json!(info.male));
+            values.insert(field("female"), json!(info.female));
+            values
+        };
+
+        match options.layout {
+            JsonLayout::Map => Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, _, info)| (key, Value::Object(values(info))))
+                    .collect(),
+            ),
+            JsonLayout::Records => Value::Array(
+                entries
+                    .into_iter()
+                    .map(|(key, name, info)| {
+                        let mut record = Map::new();
+                        record.insert(field("country"), json!(key));
+                        if options.key_by != KeyBy::Name {
+                            record.insert(field("country_name"), json!(name));
+                        }
+                        record.extend(values(info));
+                        Value::Object(record)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn convert_case(snake: &str, case: FieldCase) -> String {
+    let mut words = snake.split('_');
+    let capitalize = |word: &str| -> String {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    };
+    match case {
+        FieldCase::Snake => snake.to_string(),
+        FieldCase::Camel => {
+            let first = words.next().unwrap_or_default().to_string();
+            first + words.map(capitalize).collect::<String>().as_str()
+        }
+        FieldCase::Pascal => words.map(capitalize).collect(),
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{Dataset, Sex};
+use std::fmt::Write;
+
+impl Dataset {
+    /// GitHub-flavored Markdown table ranked by overall value, limited to the `top` highest entries when given,
+    /// followed by the attribution.
+    pub fn to_markdown(&self, top: Option<usize>) -> String {
+        let mut output = String::from(
+            "| Rank | Country | All | Male | Female |\n| ---: | :--- | ---: | ---: | ---: |\n",
+        );
+        let ranked = self.ranked(Sex::All);
+        for (rank, (name, info)) in ranked.iter().take(top.unwrap_or(usize::MAX)).enumerate() {
+            // Writing to a String cannot fail
+            let _ = writeln!(
+                output,
+                "| {} | {} | {} | {} | {} |",
+                rank + 1,
+                name.replace('|', "\\|"),
+                info.all,
+                info.male,
+                info.female
+            );
+        }
+        let _ = writeln!(output, "\nData: {}", self.attribution());
+
+        output
+    }
+}
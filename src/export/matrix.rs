@@ -0,0 +1,70 @@
+use crate::{Dataset, Error, Sex};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Differences between every pair of countries, `values[row][column]` being row minus column.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ComparisonMatrix {
+    pub sex: Sex,
+    pub countries: Vec<String>,
+    pub values: Vec<Vec<f64>>,
+}
+
+impl Dataset {
+    /// Countries are looked up like `get`, and listed under their dataset names.
+    pub fn comparison_matrix(&self, countries: &[&str], sex: Sex) -> Result<ComparisonMatrix> {
+        let entries = countries
+            .iter()
+            .map(|country| {
+                self.get_key_value(country)
+                    .ok_or_else(|| Error::UnknownCountry(country.to_string()))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let values = entries
+            .iter()
+            .map(|(_, row)| {
+                entries
+                    .iter()
+                    .map(|(_, column)| row.vs(column).get(sex))
+                    .collect()
+            })
+            .collect();
+
+        Ok(ComparisonMatrix {
+            sex,
+            countries: entries.iter().map(|(name, _)| name.to_string()).collect(),
+            values,
+        })
+    }
+}
+
+impl ComparisonMatrix {
+    /// Header row of country names with an empty corner cell, then one row per country.
+    pub fn to_csv(&self) -> String {
+        let mut output = String::new();
+        for country in &self.countries {
+            output.push(',');
+            output.push_str(&csv_field(country));
+        }
+        output.push('\n');
+        for (country, row) in self.countries.iter().zip(&self.values) {
+            output.push_str(&csv_field(country));
+            for value in row {
+                // Writing to a String cannot fail
+                let _ = write!(output, ",{:.2}", value);
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,40 @@
+mod attribution;
+#[cfg(feature = "duckdb")]
+mod duckdb;
+mod geojson;
+mod json;
+mod markdown;
+mod matrix;
+mod ndjson;
+mod report;
+mod sql;
+#[cfg(feature = "template")]
+mod template;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+mod xml;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+pub use attribution::{Attribution, License};
+pub use json::{tagged, untagged, FieldCase, JsonLayout, JsonOptions, KeyBy, FORMAT_VERSION};
+pub use matrix::ComparisonMatrix;
+pub use sql::SqlDialect;
+
+// Escapes text for XML and HTML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
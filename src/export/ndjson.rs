@@ -0,0 +1,40 @@
+use crate::{CountryInfo, Dataset};
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Serialize)]
+struct Record<'a> {
+    country: &'a str,
+    #[serde(flatten)]
+    info: &'a CountryInfo,
+    attribution: &'a str,
+}
+
+impl Dataset {
+    /// One `{"country": …, "all": …, "male": …, "female": …, "attribution": …}` object per line,
+    /// sorted by name, every line crediting the source with `Attribution::text`.
+    pub fn write_ndjson<W: Write>(&self, mut writer: W) -> Result<()> {
+        let mut entries: Vec<_> = self.countries().iter().collect();
+        entries.sort_by_key(|(name, _)| *name);
+        let attribution = self.attribution().text();
+        for (country, info) in entries {
+            let record = Record {
+                country,
+                info,
+                attribution: &attribution,
+            };
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn to_ndjson(&self) -> Result<String> {
+        let mut output = Vec::new();
+        self.write_ndjson(&mut output)?;
+        Ok(String::from_utf8(output)?)
+    }
+}
@@ -0,0 +1,109 @@
+use super::escape;
+use crate::{Dataset, Sex};
+use std::fmt::Write;
+
+const BAR_HEIGHT: usize = 18;
+const LABEL_WIDTH: usize = 180;
+const CHART_WIDTH: usize = 480;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em auto;max-width:60em;color:#222}\
+table{border-collapse:collapse;width:100%}th,td{padding:.3em .6em;border-bottom:1px solid #ddd;text-align:right}\
+th:first-child,td:first-child{text-align:left}th{cursor:pointer;user-select:none;background:#f4f4f4}\
+th.asc::after{content:\" \\25B2\"}th.desc::after{content:\" \\25BC\"}svg text{font-size:12px}";
+
+// Sorts on header click, numerically when the column holds numbers
+const SCRIPT: &str = "document.querySelectorAll('th').forEach((th,i)=>th.addEventListener('click',()=>{\
+const body=th.closest('table').tBodies[0],rows=[...body.rows],asc=!th.classList.contains('asc');\
+th.parentNode.querySelectorAll('th').forEach(h=>h.classList.remove('asc','desc'));th.classList.add(asc?'asc':'desc');\
+const key=r=>{const t=r.cells[i].textContent,n=parseFloat(t);return isNaN(n)?t:n};\
+rows.sort((a,b)=>{const x=key(a),y=key(b);return (x<y?-1:x>y?1:0)*(asc?1:-1)});rows.forEach(r=>body.appendChild(r))}));";
+
+impl Dataset {
+    /// Single-file HTML page, with no external assets, showing a bar chart of the `top`
+    /// countries, a sortable table of every entry and the attribution.
+    pub fn to_html_report(&self, top: usize) -> String {
+        let ranked = self.ranked(Sex::All);
+        let chart: Vec<_> = ranked.iter().take(top).collect();
+        let max = chart.first().map_or(0.0, |(_, info)| info.all);
+        // Bars start 10% below the lowest value, like the terminal chart, so differences stay visible
+        let floor = chart.last().map_or(0.0, |(_, info)| info.all * 0.9);
+
+        let mut output = String::new();
+        // Writing to a String cannot fail
+        let _ = write!(
+            output,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Life expectancy by country</title>\n<style>{}</style>\n</head>\n<body>\n\
+             <h1>Life expectancy by country</h1>\n",
+            STYLE
+        );
+
+        let height = chart.len() * BAR_HEIGHT;
+        let _ = writeln!(
+            output,
+            "<h2>Top {}</h2>\n<svg width=\"{}\" height=\"{}\" role=\"img\">",
+            chart.len(),
+            LABEL_WIDTH + CHART_WIDTH + 60,
+            height
+        );
+        for (index, (name, info)) in chart.iter().enumerate() {
+            let share = if max > floor {
+                (info.all - floor) / (max - floor)
+            } else {
+                1.0
+            };
+            let y = index * BAR_HEIGHT;
+            let _ = writeln!(
+                output,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\
+                 <rect x=\"{}\" y=\"{}\" width=\"{:.1}\" height=\"{}\" fill=\"#4a7fb5\"/>\
+                 <text x=\"{:.1}\" y=\"{}\">{}</text>",
+                LABEL_WIDTH - 6,
+                y + 13,
+                escape(name),
+                LABEL_WIDTH,
+                y + 2,
+                share * CHART_WIDTH as f64,
+                BAR_HEIGHT - 4,
+                LABEL_WIDTH as f64 + share * CHART_WIDTH as f64 + 4.0,
+                y + 13,
+                info.all
+            );
+        }
+        output.push_str("</svg>\n");
+
+        output.push_str(
+            "<h2>All countries</h2>\n<table>\n<thead><tr><th>Country</th><th>All</th>\
+             <th>Male</th><th>Female</th></tr></thead>\n<tbody>\n",
+        );
+        for (name, info) in &ranked {
+            let _ = writeln!(
+                output,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(name),
+                info.all,
+                info.male,
+                info.female
+            );
+        }
+        let attribution = self.attribution();
+        let _ = write!(
+            output,
+            "</tbody>\n</table>\n<footer>Data: {} (<a href=\"{}\">{}</a>){}, \
+             <a href=\"{}\">{}</a></footer>\n<script>{}</script>\n</body>\n</html>\n",
+            escape(&attribution.source),
+            escape(&attribution.url),
+            escape(&attribution.url),
+            attribution
+                .retrieved
+                .as_ref()
+                .map(|date| format!(", retrieved {}", escape(date)))
+                .unwrap_or_default(),
+            escape(attribution.license.url()),
+            escape(attribution.license.name()),
+            SCRIPT
+        );
+
+        output
+    }
+}
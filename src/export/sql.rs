@@ -0,0 +1,63 @@
+use crate::countries::country_metadata;
+use crate::Dataset;
+use std::fmt::Write;
+
+const TABLE: &str = "life_expectancy";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    Sqlite,
+    Postgres,
+}
+
+impl SqlDialect {
+    fn real(self) -> &'static str {
+        match self {
+            SqlDialect::Sqlite => "REAL",
+            SqlDialect::Postgres => "DOUBLE PRECISION",
+        }
+    }
+}
+
+impl Dataset {
+    /// `DROP`/`CREATE TABLE life_expectancy` followed by one `INSERT` per entry, in a single
+    /// transaction, so the dump can be replayed with `sqlite3 db < dump.sql` or `psql < dump.sql`.
+    /// Starts with the attribution as a comment.
+    pub fn export_sql(&self, dialect: SqlDialect) -> String {
+        let mut entries: Vec<_> = self.countries().iter().collect();
+        entries.sort_by_key(|(name, _)| *name);
+
+        // `all` is reserved, the columns are quoted in both dialects
+        let mut output = format!("-- Data: {}\nBEGIN;\n", self.attribution());
+        // Writing to a String cannot fail
+        let _ = writeln!(
+            output,
+            "DROP TABLE IF EXISTS {table};\nCREATE TABLE {table} (\n    \"country\" TEXT PRIMARY KEY,\n    \
+             \"iso3\" CHAR(3),\n    \"all\" {real} NOT NULL,\n    \"male\" {real} NOT NULL,\n    \
+             \"female\" {real} NOT NULL\n);",
+            table = TABLE,
+            real = dialect.real()
+        );
+        for (name, info) in entries {
+            let iso3 = country_metadata(name)
+                .map_or_else(|| "NULL".to_string(), |metadata| quote(&metadata.iso3));
+            let _ = writeln!(
+                output,
+                "INSERT INTO {} (\"country\", \"iso3\", \"all\", \"male\", \"female\") VALUES ({}, {}, {}, {}, {});",
+                TABLE,
+                quote(name),
+                iso3,
+                info.all,
+                info.male,
+                info.female
+            );
+        }
+        output.push_str("COMMIT;\n");
+
+        output
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
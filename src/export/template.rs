@@ -0,0 +1,50 @@
+use crate::{Dataset, Sex, COMMON};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use tera::{Context, Tera};
+
+impl Dataset {
+    /// Renders a Tera template, which sees
+    /// - `countries`, every entry but "Common" ranked by overall value, each with `rank`, `name`,
+    ///   `all`, `male`, `female`, `gap` and `ratio`, null where `male` is 0
+    /// - `by_name`, the same entries keyed by name, `by_name["Japan"].all` for instance
+    /// - `common`, the "Common" average when there is one
+    /// - `attribution`, the credit line of the data
+    ///
+    /// Output is not escaped, whatever the template is meant to produce.
+    pub fn render_template(&self, template: &str) -> Result<String> {
+        let countries: Vec<Value> = self
+            .ranked(Sex::All)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, info))| {
+                json!({
+                    "rank": index + 1,
+                    "name": name,
+                    "all": info.all,
+                    "male": info.male,
+                    "female": info.female,
+                    "gap": info.gap(),
+                    "ratio": info.female_to_male_ratio(),
+                })
+            })
+            .collect();
+        let by_name: Map<String, Value> = countries
+            .iter()
+            .map(|country| {
+                (
+                    country["name"].as_str().unwrap_or_default().to_string(),
+                    country.clone(),
+                )
+            })
+            .collect();
+
+        let mut context = Context::new();
+        context.insert("countries", &countries);
+        context.insert("by_name", &by_name);
+        context.insert("common", &self.countries().get(COMMON));
+        context.insert("attribution", &self.attribution().text());
+
+        Ok(Tera::one_off(template, &context, false)?)
+    }
+}
@@ -0,0 +1,21 @@
+use crate::lookup::CountryName;
+use crate::{CountryInfo, Dataset};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+impl Dataset {
+    /// One `["Country"]` table per country, sorted by name, as Hugo and Zola expect in their data directories.
+    /// The attribution comes first as a comment.
+    pub fn to_toml(&self) -> Result<String> {
+        let sorted: BTreeMap<&CountryName, &CountryInfo> = self.countries().iter().collect();
+        Ok(format!(
+            "# Data: {}\n{}",
+            self.attribution(),
+            toml::to_string(&sorted)?
+        ))
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Dataset> {
+        Ok(toml::from_str(toml)?)
+    }
+}
@@ -0,0 +1,103 @@
+use crate::export::Attribution;
+#[cfg(feature = "history")]
+use crate::history::Series;
+use crate::{CountryInfo, Dataset};
+use anyhow::Result;
+use rust_xlsxwriter::{Format, Workbook, Worksheet};
+#[cfg(feature = "history")]
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const HEADERS: [&str; 4] = ["Country", "All", "Male", "Female"];
+
+impl Dataset {
+    /// Workbook with a "Snapshot" sheet, sorted by name, and an "Attribution" sheet.
+    pub fn to_xlsx(&self, path: &Path) -> Result<()> {
+        let mut workbook = Workbook::new();
+        self.write_snapshot(&mut workbook)?;
+        write_attribution(&mut workbook, &[self.attribution()])?;
+        workbook.save(path)?;
+
+        Ok(())
+    }
+
+    /// Same as `to_xlsx`, with one sheet per year of `history`, oldest first, before the attribution.
+    #[cfg(feature = "history")]
+    pub fn to_xlsx_with_history(
+        &self,
+        history: &HashMap<String, Series>,
+        path: &Path,
+    ) -> Result<()> {
+        let mut workbook = Workbook::new();
+        self.write_snapshot(&mut workbook)?;
+
+        let mut years: BTreeMap<u32, Vec<(&str, &CountryInfo)>> = BTreeMap::new();
+        for (country, series) in history {
+            for (year, info) in series {
+                years
+                    .entry(*year)
+                    .or_default()
+                    .push((country.as_str(), info));
+            }
+        }
+        for (year, mut rows) in years {
+            rows.sort_by_key(|(country, _)| *country);
+            let sheet = workbook.add_worksheet();
+            sheet.set_name(year.to_string())?;
+            write_rows(sheet, &rows)?;
+        }
+        write_attribution(&mut workbook, &[self.attribution(), &Attribution::who()])?;
+        workbook.save(path)?;
+
+        Ok(())
+    }
+
+    fn write_snapshot(&self, workbook: &mut Workbook) -> Result<()> {
+        let mut rows: Vec<_> = self
+            .countries()
+            .iter()
+            .map(|(country, info)| (country.as_str(), info))
+            .collect();
+        rows.sort_by_key(|(country, _)| *country);
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("Snapshot")?;
+        write_rows(sheet, &rows)
+    }
+}
+
+fn write_attribution(workbook: &mut Workbook, attributions: &[&Attribution]) -> Result<()> {
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Attribution")?;
+    let bold = Format::new().set_bold();
+    for (column, header) in ["Source", "URL", "License", "Retrieved"].iter().enumerate() {
+        sheet.write_string_with_format(0, column as u16, *header, &bold)?;
+    }
+    for (index, attribution) in attributions.iter().enumerate() {
+        let row = index as u32 + 1;
+        sheet.write_string(row, 0, &attribution.source)?;
+        sheet.write_string(row, 1, &attribution.url)?;
+        sheet.write_string(row, 2, attribution.license.name())?;
+        sheet.write_string(row, 3, attribution.retrieved.as_deref().unwrap_or_default())?;
+    }
+    sheet.set_column_width(0, 48)?;
+
+    Ok(())
+}
+
+fn write_rows(sheet: &mut Worksheet, rows: &[(&str, &CountryInfo)]) -> Result<()> {
+    let bold = Format::new().set_bold();
+    for (column, header) in HEADERS.iter().enumerate() {
+        sheet.write_string_with_format(0, column as u16, *header, &bold)?;
+    }
+    for (index, (country, info)) in rows.iter().enumerate() {
+        let row = index as u32 + 1;
+        sheet.write_string(row, 0, *country)?;
+        sheet.write_number(row, 1, info.all)?;
+        sheet.write_number(row, 2, info.male)?;
+        sheet.write_number(row, 3, info.female)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.set_column_width(0, 32)?;
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use super::escape;
+use crate::countries::country_metadata;
+use crate::Dataset;
+use std::fmt::Write;
+
+impl Dataset {
+    /// Fixed schema, sorted by name, `iso3` being omitted for entries without a code:
+    ///
+    /// ```xml
+    /// <lifeExpectancy license="CC BY-SA 4.0" attribution="Wikipedia, …">
+    ///   <country name="Japan" iso3="JPN">
+    ///     <all>84.5</all>
+    ///     <male>81.5</male>
+    ///     <female>87.6</female>
+    ///   </country>
+    /// </lifeExpectancy>
+    /// ```
+    pub fn to_xml(&self) -> String {
+        let mut entries: Vec<_> = self.countries().iter().collect();
+        entries.sort_by_key(|(name, _)| *name);
+
+        let attribution = self.attribution();
+        let mut output = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<lifeExpectancy license=\"{}\" attribution=\"{}\">\n",
+            escape(attribution.license.name()),
+            escape(&attribution.text())
+        );
+        for (name, info) in entries {
+            // Writing to a String cannot fail
+            let _ = write!(output, "  <country name=\"{}\"", escape(name));
+            if let Some(metadata) = country_metadata(name) {
+                let _ = write!(output, " iso3=\"{}\"", escape(&metadata.iso3));
+            }
+            let _ = writeln!(
+                output,
+                ">\n    <all>{}</all>\n    <male>{}</male>\n    <female>{}</female>\n  </country>",
+                info.all, info.male, info.female
+            );
+        }
+        output.push_str("</lifeExpectancy>\n");
+
+        output
+    }
+}
@@ -0,0 +1,21 @@
+use crate::lookup::CountryName;
+use crate::{CountryInfo, Dataset};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+impl Dataset {
+    /// One mapping per country, sorted by name so the output diffs cleanly between snapshots.
+    /// The attribution comes first as a comment.
+    pub fn to_yaml(&self) -> Result<String> {
+        let sorted: BTreeMap<&CountryName, &CountryInfo> = self.countries().iter().collect();
+        Ok(format!(
+            "# Data: {}\n{}",
+            self.attribution(),
+            serde_yaml::to_string(&sorted)?
+        ))
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Dataset> {
+        Ok(serde_yaml::from_str(yaml)?)
+    }
+}
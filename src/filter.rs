@@ -0,0 +1,330 @@
+use anyhow::{anyhow, bail, Result};
+use lifespan_crawler::Comparison;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+use std::sync::OnceLock;
+
+/// A jq subset: `.`, `.key`, `."key"`, `.[0]`, `.[-1]`, `.[]`, `keys`, `length` and
+/// `select(<path> <op> <literal>)`, chained directly or with `|`.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Key(String),
+    Index(i64),
+    Iterate,
+    Keys,
+    Length,
+    Select(Vec<Step>, Comparison, Value),
+}
+
+static FILTER: OnceLock<Filter> = OnceLock::new();
+
+pub fn init(filter: Option<Filter>) {
+    if let Some(filter) = filter {
+        let _ = FILTER.set(filter);
+    }
+}
+
+/// What `--filter` makes of a document, the document itself without one.
+pub fn apply(value: Value) -> Vec<Value> {
+    match FILTER.get() {
+        Some(filter) => filter.apply(value),
+        None => vec![value],
+    }
+}
+
+impl Filter {
+    pub fn apply(&self, value: Value) -> Vec<Value> {
+        run(&self.steps, vec![value])
+    }
+}
+
+fn run(steps: &[Step], values: Vec<Value>) -> Vec<Value> {
+    steps.iter().fold(values, |values, step| {
+        values
+            .into_iter()
+            .flat_map(|value| step.apply(value))
+            .collect()
+    })
+}
+
+impl Step {
+    // Like jq, a missing key or index is null, but so is a key of something not an object
+    fn apply(&self, value: Value) -> Vec<Value> {
+        match self {
+            Step::Key(key) => vec![value.get(key).cloned().unwrap_or(Value::Null)],
+            Step::Index(index) => {
+                let item = value.as_array().and_then(|items| {
+                    let index = if *index < 0 {
+                        items.len().checked_sub(index.unsigned_abs() as usize)?
+                    } else {
+                        *index as usize
+                    };
+                    items.get(index).cloned()
+                });
+                vec![item.unwrap_or(Value::Null)]
+            }
+            Step::Iterate => match value {
+                Value::Array(items) => items,
+                Value::Object(entries) => entries.into_iter().map(|(_, item)| item).collect(),
+                _ => Vec::new(),
+            },
+            Step::Keys => match value {
+                Value::Object(entries) => vec![entries.keys().cloned().map(Value::from).collect()],
+                Value::Array(items) => vec![(0..items.len()).map(Value::from).collect()],
+                _ => vec![Value::Null],
+            },
+            Step::Length => vec![match &value {
+                Value::Array(items) => Value::from(items.len()),
+                Value::Object(entries) => Value::from(entries.len()),
+                Value::String(text) => Value::from(text.chars().count()),
+                Value::Null => Value::from(0),
+                other => other.clone(),
+            }],
+            Step::Select(path, comparison, literal) => {
+                let found = run(path, vec![value.clone()]);
+                let matches = found
+                    .first()
+                    .is_some_and(|found| match compare(found, literal) {
+                        Some(ordering) => holds(*comparison, ordering),
+                        None => *comparison == Comparison::NotEqual,
+                    });
+                if matches {
+                    vec![value]
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+    }
+}
+
+// Numbers and strings are ordered, anything else is only equal to itself
+fn compare(value: &Value, literal: &Value) -> Option<Ordering> {
+    match (value, literal) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (a, b) if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn holds(comparison: Comparison, ordering: Ordering) -> bool {
+    match comparison {
+        Comparison::Less => ordering == Ordering::Less,
+        Comparison::LessOrEqual => ordering != Ordering::Greater,
+        Comparison::Equal => ordering == Ordering::Equal,
+        Comparison::NotEqual => ordering != Ordering::Equal,
+        Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        Comparison::Greater => ordering == Ordering::Greater,
+    }
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.chars().peekable();
+        let mut steps = Vec::new();
+        loop {
+            skip_whitespace(&mut chars);
+            steps.extend(term(&mut chars)?);
+            skip_whitespace(&mut chars);
+            match chars.next() {
+                Some('|') => continue,
+                Some(c) => bail!("Unexpected {:?} in filter", c),
+                None => break,
+            }
+        }
+
+        Ok(Filter { steps })
+    }
+}
+
+type Input<'a> = Peekable<Chars<'a>>;
+
+fn skip_whitespace(chars: &mut Input) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn term(chars: &mut Input) -> Result<Vec<Step>> {
+    match chars.peek() {
+        Some('.') => path(chars),
+        Some(c) if c.is_alphabetic() => {
+            let name = identifier(chars);
+            match name.as_str() {
+                "keys" => Ok(vec![Step::Keys]),
+                "length" => Ok(vec![Step::Length]),
+                "select" => select(chars),
+                _ => bail!("Unknown filter function: {}", name),
+            }
+        }
+        Some(c) => bail!("Unexpected {:?} in filter", c),
+        None => bail!("Empty filter"),
+    }
+}
+
+// `.` alone, or `.a.b[0]["c"][]`
+fn path(chars: &mut Input) -> Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    let mut first = true;
+    loop {
+        match chars.peek() {
+            Some('.') => {
+                chars.next();
+                match chars.peek() {
+                    Some('"') => steps.push(Step::Key(quoted(chars)?)),
+                    Some('[') => {}
+                    Some(c) if is_identifier(*c) => steps.push(Step::Key(identifier(chars))),
+                    _ if first => {}
+                    _ => bail!("Expected a key after ."),
+                }
+            }
+            Some('[') => {
+                chars.next();
+                skip_whitespace(chars);
+                match chars.peek() {
+                    Some(']') => steps.push(Step::Iterate),
+                    Some('"') => steps.push(Step::Key(quoted(chars)?)),
+                    _ => {
+                        let number: String = std::iter::from_fn(|| {
+                            chars.next_if(|c| *c == '-' || c.is_ascii_digit())
+                        })
+                        .collect();
+                        let index = number
+                            .parse()
+                            .map_err(|_| anyhow!("Invalid index in filter: {:?}", number))?;
+                        steps.push(Step::Index(index));
+                    }
+                }
+                skip_whitespace(chars);
+                if chars.next() != Some(']') {
+                    bail!("Expected ] in filter");
+                }
+            }
+            _ => break,
+        }
+        first = false;
+    }
+
+    Ok(steps)
+}
+
+fn select(chars: &mut Input) -> Result<Vec<Step>> {
+    skip_whitespace(chars);
+    if chars.next() != Some('(') {
+        bail!("Expected ( after select");
+    }
+    skip_whitespace(chars);
+    let path = path(chars)?;
+    skip_whitespace(chars);
+    let operator: String =
+        std::iter::from_fn(|| chars.next_if(|c| ['<', '>', '=', '!'].contains(c))).collect();
+    let comparison = operator.parse()?;
+    skip_whitespace(chars);
+    let literal = literal(chars)?;
+    skip_whitespace(chars);
+    if chars.next() != Some(')') {
+        bail!("Expected ) to close select");
+    }
+
+    Ok(vec![Step::Select(path, comparison, literal)])
+}
+
+fn literal(chars: &mut Input) -> Result<Value> {
+    if chars.peek() == Some(&'"') {
+        return Ok(Value::from(quoted(chars)?));
+    }
+    let word: String = std::iter::from_fn(|| {
+        chars.next_if(|c| c.is_alphanumeric() || ['-', '+', '.'].contains(c))
+    })
+    .collect();
+    serde_json::from_str(&word).map_err(|_| anyhow!("Invalid literal in filter: {:?}", word))
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn identifier(chars: &mut Input) -> String {
+    std::iter::from_fn(|| chars.next_if(|c| is_identifier(*c))).collect()
+}
+
+fn quoted(chars: &mut Input) -> Result<String> {
+    chars.next();
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(text),
+            Some('\\') => text.extend(chars.next()),
+            Some(c) => text.push(c),
+            None => bail!("Unterminated string in filter"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(filter: &str, value: Value) -> Vec<Value> {
+        filter.parse::<Filter>().unwrap().apply(value)
+    }
+
+    #[test]
+    fn applies_paths() {
+        let value = json!({"data": {"Japan": {"all": 84.3}, "big key": [1, 2, 3]}});
+        assert_eq!(run(".", value.clone()), vec![value.clone()]);
+        assert_eq!(run(".data.Japan.all", value.clone()), vec![json!(84.3)]);
+        assert_eq!(run(".data.\"big key\"[-1]", value.clone()), vec![json!(3)]);
+        assert_eq!(run(".data[\"big key\"][0]", value.clone()), vec![json!(1)]);
+        assert_eq!(
+            run(".data[\"big key\"][]", value.clone()),
+            vec![json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(run(".missing.key", value), vec![Value::Null]);
+    }
+
+    #[test]
+    fn applies_functions_and_pipes() {
+        let value = json!([{"country": "Japan", "all": 84.3}, {"country": "Chad", "all": 52.5}]);
+        assert_eq!(
+            run(".[] | select(.all >= 80) | .country", value.clone()),
+            vec![json!("Japan")]
+        );
+        assert_eq!(
+            run(".[] | select(.country != \"Japan\") | .all", value.clone()),
+            vec![json!(52.5)]
+        );
+        assert_eq!(run("length", value.clone()), vec![json!(2)]);
+        assert_eq!(run(".[0] | keys", value), vec![json!(["all", "country"])]);
+    }
+
+    #[test]
+    fn rejects_malformed_filters() {
+        for filter in [
+            "",
+            "data",
+            ".a.",
+            ".[x]",
+            ".[0",
+            ".\"open",
+            "select(.a > )",
+            "select(.a ~ 1)",
+            "select .a > 1",
+            "select(.a > 1",
+            ". | ",
+            ". .",
+            "map(.a)",
+        ] {
+            assert!(filter.parse::<Filter>().is_err(), "{:?} parsed", filter);
+        }
+    }
+}
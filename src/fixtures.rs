@@ -0,0 +1,105 @@
+//! Deterministic fakes for testing code that depends on this crate, enabled by the `test-fixtures` feature.
+
+use crate::cache::CacheStore;
+use crate::source::DataSource;
+use crate::{calculate_common, CountryInfo, COMMON};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Small fixed dataset taken from `default_expectancy.json`: four countries plus their "Common" average.
+pub fn fixture_data() -> HashMap<String, CountryInfo> {
+    let mut data: HashMap<String, CountryInfo> = [
+        ("Japan", 84.83, 81.92, 87.9),
+        ("Germany", 81.51, 79.15, 84.0),
+        ("United States", 78.38, 76.01, 80.86),
+        ("Nigeria", 61.33, 59.51, 63.27),
+    ]
+    .into_iter()
+    .map(|(name, all, male, female)| (name.to_string(), CountryInfo { all, male, female }))
+    .collect();
+    data.insert(COMMON.to_string(), calculate_common(&data));
+
+    data
+}
+
+/// Source returning a fixed dataset, or always failing to simulate a network outage.
+pub struct FakeSource {
+    data: Option<HashMap<String, CountryInfo>>,
+    calls: AtomicUsize,
+}
+
+impl FakeSource {
+    pub fn new() -> Self {
+        FakeSource::with_data(fixture_data())
+    }
+
+    pub fn with_data(data: HashMap<String, CountryInfo>) -> Self {
+        FakeSource {
+            data: Some(data),
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn failing() -> Self {
+        FakeSource {
+            data: None,
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of `fetch` calls so far.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for FakeSource {
+    fn default() -> Self {
+        FakeSource::new()
+    }
+}
+
+impl DataSource for FakeSource {
+    fn fetch(&self) -> Result<HashMap<String, CountryInfo>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.data
+            .clone()
+            .ok_or_else(|| anyhow!("FakeSource is set to fail"))
+    }
+}
+
+/// In-memory cache, empty unless seeded.
+#[derive(Default)]
+pub struct FakeCache {
+    data: Mutex<Option<HashMap<String, CountryInfo>>>,
+}
+
+impl FakeCache {
+    pub fn new() -> Self {
+        FakeCache::default()
+    }
+
+    pub fn with_data(data: HashMap<String, CountryInfo>) -> Self {
+        FakeCache {
+            data: Mutex::new(Some(data)),
+        }
+    }
+
+    /// What has been stored so far, if anything.
+    pub fn stored(&self) -> Option<HashMap<String, CountryInfo>> {
+        self.data.lock().unwrap().clone()
+    }
+}
+
+impl CacheStore for FakeCache {
+    fn load(&self) -> Result<Option<HashMap<String, CountryInfo>>> {
+        Ok(self.data.lock().unwrap().clone())
+    }
+
+    fn store(&self, data: &HashMap<String, CountryInfo>) -> Result<()> {
+        *self.data.lock().unwrap() = Some(data.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+//! Entry points for the `cargo fuzz` targets under `fuzz/`, enabled by the `fuzzing` feature.
+
+use crate::html::extract_tables;
+use crate::parse_html;
+use std::io::{self, Read};
+
+/// Feeds `data` through `extract_tables` and `parse_html` the way a fetched page goes, and
+/// `parse_html` on its own. The first byte picks how many bytes each read returns, so tags
+/// split across chunk boundaries get exercised.
+pub fn parse_page(data: &[u8]) {
+    let Some((&step, page)) = data.split_first() else {
+        return;
+    };
+    let reader = Chunked {
+        data: page,
+        step: usize::from(step).max(1),
+    };
+    if let Ok(tables) = extract_tables(reader) {
+        let _ = parse_html(&tables);
+    }
+    let _ = parse_html(&String::from_utf8_lossy(page));
+}
+
+struct Chunked<'a> {
+    data: &'a [u8],
+    step: usize,
+}
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.step.min(buf.len()).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
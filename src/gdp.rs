@@ -0,0 +1,98 @@
+use crate::countries::country_metadata;
+use crate::parallel::join;
+use crate::{get_data, CountryInfo, Sex};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// World Bank "GDP per capita (current US$)", most recent non-empty value per economy
+const GDP_URL: &str = "https://api.worldbank.org/v2/country/all/indicator/NY.GDP.PCAP.CD?format=json&per_page=20000&mrnev=1";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CountryIndicators {
+    pub life_expectancy: CountryInfo,
+    pub gdp_pc: f64,
+}
+
+#[derive(Deserialize)]
+struct WorldBankRow {
+    countryiso3code: String,
+    value: Option<f64>,
+}
+
+/// GDP per capita keyed by ISO 3166-1 alpha-3 code.
+pub fn fetch_gdp_per_capita() -> Result<HashMap<String, f64>> {
+    // The response is `[paging, rows]`
+    let resp = reqwest::blocking::get(GDP_URL)?
+        .error_for_status()?
+        .json::<Vec<Value>>()?;
+    let rows = resp
+        .into_iter()
+        .nth(1)
+        .ok_or_else(|| anyhow!("Unexpected World Bank response"))?;
+    let rows = serde_json::from_value::<Vec<WorldBankRow>>(rows)?;
+
+    Ok(rows
+        .into_iter()
+        .filter(|row| !row.countryiso3code.is_empty())
+        .filter_map(|row| Some((row.countryiso3code, row.value?)))
+        .collect())
+}
+
+/// Joins on ISO codes from the bundled metadata, entries missing on either side are dropped.
+pub fn join_gdp(
+    content: &HashMap<String, CountryInfo>,
+    gdp: &HashMap<String, f64>,
+) -> HashMap<String, CountryIndicators> {
+    content
+        .iter()
+        .filter_map(|(name, info)| {
+            let gdp_pc = *gdp.get(&country_metadata(name)?.iso3)?;
+            let indicators = CountryIndicators {
+                life_expectancy: info.clone(),
+                gdp_pc,
+            };
+            Some((name.clone(), indicators))
+        })
+        .collect()
+}
+
+pub fn get_indicators() -> Result<HashMap<String, CountryIndicators>> {
+    let (content, gdp) = join(get_data, fetch_gdp_per_capita);
+    Ok(join_gdp(&content?, &gdp?))
+}
+
+/// Pearson correlation between life expectancy and ln(GDP per capita), the Preston curve being log-linear.
+pub fn correlation(indicators: &HashMap<String, CountryIndicators>, sex: Sex) -> Option<f64> {
+    let (xs, ys): (Vec<f64>, Vec<f64>) = indicators
+        .values()
+        .filter(|indicator| indicator.gdp_pc > 0.0)
+        .map(|indicator| (indicator.gdp_pc.ln(), indicator.life_expectancy.get(sex)))
+        .unzip();
+
+    pearson(&xs, &ys)
+}
+
+pub fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() != ys.len() || xs.len() < 2 {
+        return None;
+    }
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+
+    let mut covariance = 0.0;
+    let mut variance_x = 0.0;
+    let mut variance_y = 0.0;
+    for (x, y) in xs.iter().zip(ys) {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x).powi(2);
+        variance_y += (y - mean_y).powi(2);
+    }
+    if variance_x == 0.0 || variance_y == 0.0 {
+        return None;
+    }
+
+    Some(covariance / (variance_x * variance_y).sqrt())
+}
@@ -0,0 +1,44 @@
+use crate::countries::country_by_code;
+use anyhow::Result;
+#[cfg(feature = "blocking")]
+use serde_json::Value;
+use std::net::IpAddr;
+use std::path::Path;
+
+// Answers `{"ip": "…", "country": "DE"}` for the caller's address
+pub const DEFAULT_SERVICE_URL: &str = "https://api.country.is/";
+
+/// Asks a GeoIP web service for the caller's country. This sends the caller's
+/// IP address to a third party, so it is never done implicitly.
+///
+/// Accepts either a plain ISO code body or JSON with a `country`/`country_code` field.
+#[cfg(feature = "blocking")]
+pub fn country_from_service(url: &str) -> Result<Option<&'static str>> {
+    let body = reqwest::blocking::get(url)?.error_for_status()?.text()?;
+    let body = body.trim();
+    let code = if body.starts_with('{') {
+        let json = serde_json::from_str::<Value>(body)?;
+        ["country", "country_code"]
+            .iter()
+            .find_map(|key| json.get(*key)?.as_str().map(str::to_string))
+    } else {
+        Some(body.to_string())
+    };
+
+    Ok(code.and_then(|code| country_by_code(&code)))
+}
+
+/// Looks `ip` up in a local MaxMind GeoLite2/GeoIP2 country or city database.
+pub fn country_from_database(path: &Path, ip: IpAddr) -> Result<Option<&'static str>> {
+    let reader = maxminddb::Reader::open_readfile(path)?;
+    let country = match reader.lookup::<maxminddb::geoip2::Country>(ip) {
+        Ok(country) => country,
+        Err(maxminddb::MaxMindDBError::AddressNotFoundError(_)) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(country
+        .country
+        .and_then(|country| country.iso_code)
+        .and_then(country_by_code))
+}
@@ -0,0 +1,58 @@
+use crate::Sex;
+use anyhow::Result;
+use serde::Deserialize;
+
+// WHO Global Health Observatory OData API
+const GHO_API_URL: &str = "https://ghoapi.azureedge.net/api";
+
+#[derive(Deserialize)]
+struct GhoResponse {
+    value: Vec<GhoFact>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct GhoFact {
+    #[cfg(feature = "history")]
+    #[serde(rename = "SpatialDim")]
+    pub country: Option<String>,
+    #[serde(rename = "TimeDim")]
+    pub year: u32,
+    #[cfg(feature = "history")]
+    #[serde(rename = "Dim1")]
+    pub sex: Option<String>,
+    #[cfg(feature = "life-table")]
+    #[serde(rename = "Dim2")]
+    pub age_group: Option<String>,
+    #[serde(rename = "NumericValue")]
+    pub value: Option<f64>,
+}
+
+pub(crate) fn fetch_indicator(indicator: &str, filter: Option<String>) -> Result<Vec<GhoFact>> {
+    let mut request =
+        reqwest::blocking::Client::new().get(format!("{}/{}", GHO_API_URL, indicator));
+    if let Some(filter) = filter {
+        request = request.query(&[("$filter", filter)]);
+    }
+    let resp = request.send()?.error_for_status()?.json::<GhoResponse>()?;
+
+    Ok(resp.value)
+}
+
+#[cfg(any(feature = "history", feature = "life-table"))]
+pub(crate) fn sex_code(sex: Sex) -> &'static str {
+    match sex {
+        Sex::All => "SEX_BTSX",
+        Sex::Male => "SEX_MLE",
+        Sex::Female => "SEX_FMLE",
+    }
+}
+
+#[cfg(feature = "history")]
+pub(crate) fn parse_sex(code: &str) -> Option<Sex> {
+    match code {
+        "SEX_BTSX" => Some(Sex::All),
+        "SEX_MLE" => Some(Sex::Male),
+        "SEX_FMLE" => Some(Sex::Female),
+        _ => None,
+    }
+}
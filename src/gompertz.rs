@@ -0,0 +1,99 @@
+use crate::{get_data, Error, Sex};
+use anyhow::{anyhow, Result};
+
+// Typical adult mortality slope and background hazard for modern populations
+pub(crate) const DEFAULT_BETA: f64 = 0.085;
+pub(crate) const DEFAULT_LAMBDA: f64 = 0.0005;
+const MAX_AGE: f64 = 130.0;
+const STEP: f64 = 0.25;
+
+/// Gompertz–Makeham mortality law, hazard `μ(x) = alpha * e^(beta * x) + lambda`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GompertzMakeham {
+    pub alpha: f64,
+    pub beta: f64,
+    pub lambda: f64,
+}
+
+impl GompertzMakeham {
+    /// Fits `alpha` so that the curve reproduces the given life expectancy at birth.
+    pub fn fit(life_expectancy: f64) -> Result<Self> {
+        Self::fit_with(life_expectancy, DEFAULT_BETA, DEFAULT_LAMBDA)
+    }
+
+    pub fn fit_with(life_expectancy: f64, beta: f64, lambda: f64) -> Result<Self> {
+        if !(1.0..MAX_AGE).contains(&life_expectancy) {
+            return Err(anyhow!(
+                "Life expectancy {} is out of range",
+                life_expectancy
+            ));
+        }
+
+        // Life expectancy decreases monotonically with alpha, bisect on ln(alpha)
+        let (mut low, mut high) = (-30.0_f64, 0.0_f64);
+        for _ in 0..100 {
+            let mid = (low + high) / 2.0;
+            let model = GompertzMakeham {
+                alpha: mid.exp(),
+                beta,
+                lambda,
+            };
+            if model.life_expectancy() > life_expectancy {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Ok(GompertzMakeham {
+            alpha: ((low + high) / 2.0).exp(),
+            beta,
+            lambda,
+        })
+    }
+
+    pub fn hazard(&self, age: f64) -> f64 {
+        self.alpha * (self.beta * age).exp() + self.lambda
+    }
+
+    /// Probability of surviving from birth to `age`.
+    pub fn survival(&self, age: f64) -> f64 {
+        let age = age.max(0.0);
+        (-self.lambda * age - self.alpha / self.beta * ((self.beta * age).exp() - 1.0)).exp()
+    }
+
+    /// Probability that someone alive at `from_age` is still alive at `to_age`.
+    pub fn survival_probability(&self, from_age: f64, to_age: f64) -> f64 {
+        if to_age <= from_age {
+            return 1.0;
+        }
+        let from = self.survival(from_age);
+        if from == 0.0 {
+            return 0.0;
+        }
+
+        self.survival(to_age) / from
+    }
+
+    pub fn life_expectancy(&self) -> f64 {
+        // Trapezoidal integration of the survival curve
+        let steps = (MAX_AGE / STEP) as usize;
+        (0..steps)
+            .map(|i| {
+                let age = i as f64 * STEP;
+                (self.survival(age) + self.survival(age + STEP)) / 2.0 * STEP
+            })
+            .sum()
+    }
+}
+
+/// Approximate probability of surviving from `from_age` to `to_age`, fitted to the point estimate.
+pub fn survival_probability(country: &str, sex: Sex, from_age: f64, to_age: f64) -> Result<f64> {
+    let data = get_data()?;
+    let info = data
+        .get(country)
+        .ok_or_else(|| Error::UnknownCountry(country.to_string()))?;
+    let model = GompertzMakeham::fit(info.get(sex))?;
+
+    Ok(model.survival_probability(from_age, to_age))
+}
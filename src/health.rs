@@ -0,0 +1,137 @@
+//! `/healthz` and `/readyz` for a long-running client, `lifespan watch --health` for instance,
+//! so it can sit behind Kubernetes probes.
+
+use crate::client::{Client, FetchOutcome, Origin};
+use crate::{tagged, FETCH_URL};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, warn};
+
+// Probes come every few seconds, the article is checked at most this often
+const PROBE_TTL: Duration = Duration::from_secs(300);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HealthReport {
+    /// Whether there is cached data to answer from, what `/readyz` reports.
+    pub ready: bool,
+    /// Seconds since the cache was last written.
+    pub cache_age_secs: Option<u64>,
+    pub last_source: Option<Origin>,
+    pub last_fetch: Option<FetchOutcome>,
+    /// Whether the Wikipedia article answered the last check, made every few minutes in the
+    /// background. `None` until the first one is done.
+    pub source_reachable: Option<bool>,
+}
+
+/// How `client` is doing. An unreachable source or a failed fetch still leaves it ready as long
+/// as the cache has data.
+pub fn report(client: &Client) -> HealthReport {
+    let stats = client.cache_stats();
+    let cache_age = stats
+        .last_refresh
+        .map(|at| SystemTime::now().duration_since(at).unwrap_or_default());
+    HealthReport {
+        ready: cache_age.is_some(),
+        cache_age_secs: cache_age.map(|age| age.as_secs()),
+        last_source: stats.last_source,
+        last_fetch: stats.last_fetch,
+        source_reachable: source_reachable(),
+    }
+}
+
+// Last answer of the article, probed in the background so a probe never waits on the network
+fn source_reachable() -> Option<bool> {
+    static PROBE: Mutex<(Option<Instant>, Option<bool>)> = Mutex::new((None, None));
+    let mut probe = PROBE.lock().unwrap_or_else(PoisonError::into_inner);
+    if probe.0.is_none_or(|at| at.elapsed() >= PROBE_TTL) {
+        probe.0 = Some(Instant::now());
+        thread::spawn(|| {
+            let reachable = reqwest::blocking::Client::builder()
+                .timeout(PROBE_TIMEOUT)
+                .build()
+                .and_then(|http| http.head(FETCH_URL).send())
+                .is_ok_and(|resp| resp.status().is_success());
+            PROBE.lock().unwrap_or_else(PoisonError::into_inner).1 = Some(reachable);
+        });
+    }
+
+    probe.1
+}
+
+/// Answers probes on `listener` until it fails, one connection at a time.
+pub fn serve(listener: &TcpListener, client: &Client) -> Result<()> {
+    for stream in listener.incoming() {
+        if let Err(err) = stream
+            .map_err(anyhow::Error::from)
+            .and_then(|stream| respond(stream, client))
+        {
+            warn!("health probe failed: {:#}", err);
+        }
+    }
+    Ok(())
+}
+
+fn respond(mut stream: TcpStream, client: &Client) -> Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buf)?;
+        if read == 0 || request.len() > MAX_REQUEST_BYTES {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut line = request.lines().next().unwrap_or_default().split(' ');
+    let (method, path) = (
+        line.next().unwrap_or_default(),
+        line.next().unwrap_or_default(),
+    );
+    debug!(method, path, "health probe");
+
+    let (status, body) = match (method, path) {
+        ("GET" | "HEAD", "/healthz") => ("200 OK", tagged("health", json!({ "status": "ok" }))?),
+        ("GET" | "HEAD", "/readyz") => {
+            let report = report(client);
+            let status = if report.ready {
+                "200 OK"
+            } else {
+                "503 Service Unavailable"
+            };
+            (status, tagged("health", &report)?)
+        }
+        ("GET" | "HEAD", _) => (
+            "404 Not Found",
+            tagged("error", json!({ "kind": "other", "message": "not found" }))?,
+        ),
+        _ => (
+            "405 Method Not Allowed",
+            tagged(
+                "error",
+                json!({ "kind": "other", "message": "method not allowed" }),
+            )?,
+        ),
+    };
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(body.as_bytes())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use crate::countries::country_by_iso3;
+use crate::gho::{fetch_indicator, parse_sex, sex_code};
+use crate::parallel::join_all;
+use crate::{
+    calculate_common, get_tmp_dir, read_tmp_file, write_tmp_file, CountryInfo, Error, Precision,
+    Sex, COMMON,
+};
+use anyhow::{anyhow, Result};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+// "Life expectancy at birth (years)"
+const LIFE_EXPECTANCY_INDICATOR: &str = "WHOSIS_000001";
+
+pub type Series = BTreeMap<u32, CountryInfo>;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Projection {
+    pub year: u32,
+    pub value: f64,
+    // 95% prediction interval
+    pub lower: f64,
+    pub upper: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Change {
+    pub country: String,
+    pub from_year: u32,
+    pub to_year: u32,
+    pub from: CountryInfo,
+    pub to: CountryInfo,
+    pub delta: CountryInfo,
+}
+
+pub fn get_history() -> Result<HashMap<String, Series>> {
+    let path = get_tmp_dir().join(".tmp_history.json");
+    if let Some(history) = read_tmp_file(&path)? {
+        return Ok(history);
+    }
+
+    let history = fetch_history()?;
+    write_tmp_file(&path, &history)?;
+    Ok(history)
+}
+
+/// Yearly life expectancy per country from the WHO, keyed by the same names as `get_data`.
+pub fn fetch_history() -> Result<HashMap<String, Series>> {
+    // A request per sex, fetched concurrently, rather than the whole indicator in one
+    let jobs = [Sex::All, Sex::Male, Sex::Female]
+        .map(|sex| {
+            move || {
+                let filter = format!("Dim1 eq '{}'", sex_code(sex));
+                fetch_indicator(LIFE_EXPECTANCY_INDICATOR, Some(filter))
+            }
+        })
+        .into();
+    let facts = join_all(jobs, 3)
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten();
+
+    let mut values: HashMap<(String, u32), HashMap<Sex, f64>> = HashMap::new();
+    for fact in facts {
+        let country = fact.country.as_deref().and_then(country_by_iso3);
+        let sex = fact.sex.as_deref().and_then(parse_sex);
+        if let (Some(country), Some(sex), Some(value)) = (country, sex, fact.value) {
+            values
+                .entry((country.to_string(), fact.year))
+                .or_default()
+                .insert(sex, value);
+        }
+    }
+
+    let mut result: HashMap<String, Series> = HashMap::new();
+    for ((country, year), by_sex) in values {
+        if let (Some(&all), Some(&male), Some(&female)) = (
+            by_sex.get(&Sex::All),
+            by_sex.get(&Sex::Male),
+            by_sex.get(&Sex::Female),
+        ) {
+            result
+                .entry(country)
+                .or_default()
+                .insert(year, CountryInfo { all, male, female });
+        }
+    }
+
+    Ok(result)
+}
+
+/// Every country's values for `year` with their "Common" average, empty when the year is not on record.
+pub fn year_of(history: &HashMap<String, Series>, year: u32) -> HashMap<String, CountryInfo> {
+    #[cfg(feature = "rayon")]
+    let series = history.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let series = history.iter();
+    let mut data: HashMap<String, CountryInfo> = series
+        .filter_map(|(country, series)| Some((country.clone(), series.get(&year)?.clone())))
+        .collect();
+    if !data.is_empty() {
+        data.insert(COMMON.to_string(), calculate_common(&data));
+    }
+
+    data
+}
+
+/// Per-country change from `year` to the latest available year, largest improvement first.
+pub fn changes_since(year: u32) -> Result<Vec<Change>> {
+    Ok(changes_in(&get_history()?, year))
+}
+
+pub fn changes_in(history: &HashMap<String, Series>, year: u32) -> Vec<Change> {
+    #[cfg(feature = "rayon")]
+    let series = history.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let series = history.iter();
+    let mut changes: Vec<Change> = series
+        .filter_map(|(country, series)| {
+            // Fall back to the first year on record after `year` when it is missing
+            let (&from_year, from) = series.range(year..).next()?;
+            let (&to_year, to) = series.last_key_value()?;
+            if from_year == to_year {
+                return None;
+            }
+            let delta = to.vs(from).rounded(Precision::default());
+
+            Some(Change {
+                country: country.clone(),
+                from_year,
+                to_year,
+                from: from.clone(),
+                to: to.clone(),
+                delta,
+            })
+        })
+        .collect();
+    let order = |a: &Change, b: &Change| b.delta.all.total_cmp(&a.delta.all);
+    #[cfg(feature = "rayon")]
+    changes.par_sort_by(order);
+    #[cfg(not(feature = "rayon"))]
+    changes.sort_by(order);
+
+    changes
+}
+
+pub fn project(country: &str, sex: Sex, year: u32) -> Result<Projection> {
+    let history = get_history()?;
+    let series = history
+        .get(country)
+        .ok_or_else(|| Error::UnknownCountry(country.to_string()))?;
+
+    project_series(series, sex, year)
+        .ok_or_else(|| anyhow!("Not enough history to project {}", country))
+}
+
+/// `project_series` for every country with enough history.
+pub fn project_all(
+    history: &HashMap<String, Series>,
+    sex: Sex,
+    year: u32,
+) -> HashMap<String, Projection> {
+    #[cfg(feature = "rayon")]
+    let series = history.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let series = history.iter();
+    series
+        .filter_map(|(country, series)| Some((country.clone(), project_series(series, sex, year)?)))
+        .collect()
+}
+
+/// Least-squares linear trend over the series, with a 95% prediction interval.
+pub fn project_series(series: &Series, sex: Sex, year: u32) -> Option<Projection> {
+    let points: Vec<(f64, f64)> = series
+        .iter()
+        .map(|(year, info)| (*year as f64, info.get(sex)))
+        .collect();
+    if points.len() < 3 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx = points
+        .iter()
+        .map(|(x, _)| (x - mean_x).powi(2))
+        .sum::<f64>();
+    let sxy = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>();
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+
+    let residual = points
+        .iter()
+        .map(|(x, y)| (y - (intercept + slope * x)).powi(2))
+        .sum::<f64>();
+    let std_error = (residual / (n - 2.0)).sqrt();
+    let x = year as f64;
+    let margin =
+        t_quantile_975(n - 2.0) * std_error * (1.0 + 1.0 / n + (x - mean_x).powi(2) / sxx).sqrt();
+    let value = intercept + slope * x;
+
+    Some(Projection {
+        year,
+        value,
+        lower: value - margin,
+        upper: value + margin,
+    })
+}
+
+// Cornish–Fisher expansion of Student's t around the normal quantile
+fn t_quantile_975(df: f64) -> f64 {
+    let z: f64 = 1.959964;
+    z + (z.powi(3) + z) / (4.0 * df)
+        + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * df.powi(2))
+}
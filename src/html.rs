@@ -0,0 +1,110 @@
+use anyhow::Result;
+use scraper::{ElementRef, Selector};
+use std::io::Read;
+
+/// Parses a selector known at compile time.
+pub(crate) fn selector(css: &str) -> Selector {
+    Selector::parse(css).unwrap_or_else(|err| panic!("invalid selector {:?}: {}", css, err))
+}
+
+/// Concatenated text of the element and its descendants.
+pub(crate) fn text(element: ElementRef) -> String {
+    element.text().collect()
+}
+
+const CHUNK_SIZE: usize = 16 * 1024;
+// A '<' with no '>' after this many bytes is treated as text
+const MAX_TAG_LEN: usize = 64 * 1024;
+// Section headings, kept to label the tables under them
+const HEADINGS: [&[u8]; 3] = [b"<h2", b"<h3", b"<h4"];
+const CLOSING_HEADINGS: [&[u8]; 3] = [b"</h2", b"</h3", b"</h4"];
+
+/// Streams an HTML document and keeps only its `wikitable` tables (with anything nested in them)
+/// and the section headings labeling them, so the full page never has to be held in memory.
+pub(crate) fn extract_tables<R: Read>(mut reader: R) -> Result<String> {
+    let mut output: Vec<u8> = b"<html><body>".to_vec();
+    let mut pending: Vec<u8> = Vec::with_capacity(CHUNK_SIZE);
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut depth = 0usize;
+    let mut in_heading = false;
+
+    loop {
+        let read = reader.read(&mut chunk)?;
+        let eof = read == 0;
+        pending.extend_from_slice(&chunk[..read]);
+
+        let mut pos = 0;
+        while pos < pending.len() {
+            let keep = depth > 0 || in_heading;
+            let Some(lt) = find(&pending[pos..], b'<').map(|i| pos + i) else {
+                if keep {
+                    output.extend_from_slice(&pending[pos..]);
+                }
+                pos = pending.len();
+                break;
+            };
+            if keep {
+                output.extend_from_slice(&pending[pos..lt]);
+            }
+            let Some(gt) = find(&pending[lt..], b'>').map(|i| lt + i) else {
+                if !eof && pending.len() - lt < MAX_TAG_LEN {
+                    // Incomplete tag, wait for the next chunk
+                    pos = lt;
+                    break;
+                }
+                if keep {
+                    output.extend_from_slice(&pending[lt..]);
+                }
+                pos = pending.len();
+                break;
+            };
+
+            let tag = pending[lt..=gt].to_ascii_lowercase();
+            if is_tag(&tag, b"<table") {
+                if depth > 0 || contains(&tag, b"wikitable") {
+                    depth += 1;
+                }
+            } else if is_tag(&tag, b"</table") && depth > 0 {
+                output.extend_from_slice(&pending[lt..=gt]);
+                depth -= 1;
+                pos = gt + 1;
+                continue;
+            } else if depth == 0 && HEADINGS.iter().any(|name| is_tag(&tag, name)) {
+                in_heading = true;
+            }
+            if depth > 0 || in_heading {
+                output.extend_from_slice(&pending[lt..=gt]);
+            }
+            if depth == 0 && CLOSING_HEADINGS.iter().any(|name| is_tag(&tag, name)) {
+                in_heading = false;
+            }
+            pos = gt + 1;
+        }
+        pending.drain(..pos);
+
+        if eof {
+            break;
+        }
+    }
+    output.extend_from_slice(b"</body></html>");
+
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+fn find(haystack: &[u8], needle: u8) -> Option<usize> {
+    haystack.iter().position(|&byte| byte == needle)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+// `<table` but not `<tablefoo`
+fn is_tag(tag: &[u8], name: &[u8]) -> bool {
+    tag.starts_with(name)
+        && tag
+            .get(name.len())
+            .is_some_and(|&byte| byte == b'>' || byte == b'/' || byte.is_ascii_whitespace())
+}
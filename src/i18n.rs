@@ -0,0 +1,68 @@
+use lifespan_crawler::locale::message_language;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+const MESSAGES_JSON: &str = include_str!("../cli_messages.json");
+const FALLBACK: &str = "en";
+
+type Catalog = HashMap<String, String>;
+
+fn catalogs() -> &'static HashMap<String, Catalog> {
+    static CATALOGS: OnceLock<HashMap<String, Catalog>> = OnceLock::new();
+    CATALOGS.get_or_init(|| {
+        serde_json::from_str(MESSAGES_JSON).expect("bundled cli_messages.json is valid")
+    })
+}
+
+static LANGUAGE: OnceLock<String> = OnceLock::new();
+
+/// Picks the message language from `--lang`, then `LIFESPAN_LANG`, then the system locale, English when none has a catalog.
+pub fn init(lang: Option<&str>) {
+    let language = lang
+        .map(str::to_string)
+        .or_else(|| env::var("LIFESPAN_LANG").ok())
+        .or_else(message_language)
+        .map(|lang| primary_language(&lang))
+        .filter(|lang| catalogs().contains_key(lang))
+        .unwrap_or_else(|| FALLBACK.to_string());
+    let _ = LANGUAGE.set(language);
+}
+
+pub fn language() -> &'static str {
+    LANGUAGE.get().map(String::as_str).unwrap_or(FALLBACK)
+}
+
+fn primary_language(lang: &str) -> String {
+    lang.split(['-', '_', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// The `key` message in the current language with each `{name}` replaced, falling back to English and then to the key itself.
+pub fn message(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let template = [language(), FALLBACK]
+        .iter()
+        .find_map(|lang| catalogs().get(*lang)?.get(key))
+        .map(String::as_str)
+        .unwrap_or(key);
+    let mut text = template.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+
+    text
+}
+
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::message($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message($key, &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+])
+    };
+}
+
+pub(crate) use t;
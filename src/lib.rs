@@ -1,18 +1,155 @@
 extern crate dirs;
 
-use anyhow::Result;
-use select::document::Document;
-use select::node::Node;
-use select::predicate::{Class, Name};
+#[cfg(feature = "html")]
+use anyhow::Context;
+use anyhow::{anyhow, Result};
+#[cfg(feature = "html")]
+use html::extract_tables;
+#[cfg(feature = "html")]
+use html::{selector, text};
+use schema::{parse_strict, Schema};
+#[cfg(feature = "html")]
+use scraper::{ElementRef, Html};
 use serde::{Deserialize, Serialize};
-use serde_json;
+use serde_json::value::RawValue;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+#[cfg(feature = "defaults-file")]
 use std::env;
-use std::fs::{self, metadata, File};
+use std::fmt;
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+#[cfg(feature = "blocking")]
+use std::time::Duration;
 
+pub mod cache;
+pub mod chart;
+mod client;
+#[cfg(feature = "cohort")]
+pub mod cohort;
+pub mod countries;
+#[cfg(feature = "country-enum")]
+mod country;
+mod dataset;
+mod date;
+#[cfg(feature = "decimal")]
+pub mod decimal;
+pub mod diff;
+#[cfg(feature = "blocking")]
+pub mod doctor;
+mod error;
+mod export;
+#[cfg(feature = "test-fixtures")]
+pub mod fixtures;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzzing;
+#[cfg(feature = "gdp")]
+pub mod gdp;
+#[cfg(feature = "geoip")]
+pub mod geoip;
+#[cfg(any(feature = "life-table", feature = "history"))]
+mod gho;
+pub mod gompertz;
+#[cfg(feature = "blocking")]
+pub mod health;
+#[cfg(feature = "history")]
+pub mod history;
+#[cfg(feature = "html")]
+mod html;
+#[cfg(feature = "life-table")]
+pub mod life_table;
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub mod limits;
+pub mod locale;
+pub mod lookup;
+pub mod names;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod notify;
+#[cfg(feature = "blocking")]
+mod parallel;
+#[cfg(feature = "wasm-plugins")]
+pub mod plugin;
+pub mod progress;
+pub mod provenance;
+pub mod query;
+#[cfg(any(feature = "blocking", feature = "async"))]
+mod rate_limit;
+#[cfg(feature = "arc-swap")]
+mod refreshable;
+mod region;
+#[cfg(feature = "replay")]
+pub mod replay;
+#[cfg(feature = "blocking")]
+mod robots;
+pub mod schedule;
+mod schema;
+#[cfg(feature = "scripting")]
+pub mod script;
+mod shared;
+#[cfg(feature = "defaults-file")]
+pub mod signature;
+pub mod source;
+#[cfg(feature = "subnational")]
+pub mod subnational;
+#[cfg(feature = "html")]
+pub mod tables;
+pub mod transform;
+pub mod transport;
+pub mod usage;
+
+pub use cache::{CacheStore, FileCache, IndexedCache, MemoryCache, SnapshotCache};
+pub use client::{
+    CacheStats, Client, ClientBuilder, FallbackPolicy, FetchOptions, FetchOutcome, Origin,
+    DEFAULT_FALLBACK,
+};
+pub use countries::{EntityKind, IncomeGroup, WhoRegion};
+#[cfg(feature = "country-enum")]
+pub use country::Country;
+pub use dataset::{Dataset, Summary};
+pub use diff::DataDiff;
+pub use error::{error_kind, Error, ErrorKind};
+pub use export::{
+    tagged, untagged, Attribution, ComparisonMatrix, FieldCase, JsonLayout, JsonOptions, KeyBy,
+    License, SqlDialect, FORMAT_VERSION,
+};
+pub use gompertz::survival_probability;
+pub use locale::default_country;
+pub use lookup::CountryName;
+#[cfg(feature = "async")]
+pub use nonblocking::{fetch_async, get_data_async};
+#[cfg(feature = "wasm-plugins")]
+pub use plugin::WasmPlugin;
+pub use progress::Phase;
+pub use provenance::Provenance;
+pub use query::{Comparison, Field, Query, QueryRow};
+#[cfg(feature = "arc-swap")]
+pub use refreshable::Refreshable;
+pub use region::Region;
+pub use schedule::{Cron, Schedule};
+#[cfg(feature = "scripting")]
+pub use script::RhaiScript;
+pub use shared::{global, global_refresh, SharedDataset};
+#[cfg(feature = "blocking")]
+pub use source::WikipediaSource;
+pub use source::{DataSource, OfflineSource};
+#[cfg(feature = "blocking")]
+pub use tables::{fetch_regions, get_regions};
+#[cfg(feature = "html")]
+pub use tables::{Publisher, Table, TableSelector};
+pub use transform::Transform;
+#[cfg(feature = "blocking")]
+pub use transport::ReqwestTransport;
+pub use transport::Transport;
+
+#[cfg(any(feature = "blocking", feature = "async"))]
 const FETCH_URL: &str = "https://en.wikipedia.org/wiki/List_of_countries_by_life_expectancy";
+/// Key of the average entry inserted next to the countries.
+pub const COMMON: &str = "Common";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CountryInfo {
@@ -21,64 +158,298 @@ pub struct CountryInfo {
     pub female: f64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sex {
+    All,
+    Male,
+    Female,
+}
+
+impl FromStr for Sex {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "all" | "both" => Ok(Sex::All),
+            "male" | "m" => Ok(Sex::Male),
+            "female" | "f" => Ok(Sex::Female),
+            _ => Err(anyhow!("Unknown sex: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Sex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sex::All => write!(f, "all"),
+            Sex::Male => write!(f, "male"),
+            Sex::Female => write!(f, "female"),
+        }
+    }
+}
+
+impl CountryInfo {
+    pub fn get(&self, sex: Sex) -> f64 {
+        match sex {
+            Sex::All => self.all,
+            Sex::Male => self.male,
+            Sex::Female => self.female,
+        }
+    }
+
+    /// Years women outlive men, negative where men live longer.
+    pub fn gap(&self) -> f64 {
+        self.female - self.male
+    }
+
+    /// Female over male expectancy, above 1 where women live longer. `None` when the male
+    /// value is 0, where the ratio would be infinite or NaN.
+    pub fn female_to_male_ratio(&self) -> Option<f64> {
+        (self.male != 0.0).then(|| self.female / self.male)
+    }
+
+    /// How much longer than in `other`, for each sex.
+    pub fn vs(&self, other: &CountryInfo) -> CountryInfo {
+        CountryInfo {
+            all: self.all - other.all,
+            male: self.male - other.male,
+            female: self.female - other.female,
+        }
+    }
+
+    pub fn rounded(&self, precision: Precision) -> CountryInfo {
+        CountryInfo {
+            all: precision.apply(self.all),
+            male: precision.apply(self.male),
+            female: precision.apply(self.female),
+        }
+    }
+}
+
+/// Decimal places kept in returned values, 2 by default. Values are rounded once on the
+/// way out, caches and derived entries keep the full precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// 0 to 3, more is treated as 3.
+    Decimals(u32),
+    /// Values as the source gives them, and derived ones such as the "Common" average unrounded.
+    Full,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Precision::Decimals(2)
+    }
+}
+
+impl Precision {
+    pub fn apply(self, value: f64) -> f64 {
+        match self {
+            Precision::Decimals(places) => shave_round(value, Some(places.min(3))),
+            Precision::Full => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Period,
+    #[cfg(feature = "cohort")]
+    Cohort {
+        birth_year: u32,
+    },
+}
+
+pub fn get_data_for(metric: Metric) -> Result<HashMap<String, CountryInfo>> {
+    match metric {
+        Metric::Period => get_data(),
+        #[cfg(feature = "cohort")]
+        Metric::Cohort { birth_year } => cohort::get_cohort_data(birth_year),
+    }
+}
+
 pub fn get_data() -> Result<HashMap<String, CountryInfo>> {
-    match has_cache() {
-        Ok(true) => {
-            let json = fs::read_to_string(get_tmp_file_path())?;
-            Ok(serde_json::from_str::<HashMap<String, CountryInfo>>(&json)?)
+    Client::default().get_data()
+}
+
+/// What life expectancy was in `year`, in the same shape as `get_data`.
+pub fn get_data_for_year(year: u32) -> Result<HashMap<String, CountryInfo>> {
+    Client::default().get_data_for_year(year)
+}
+
+// Set through `cache::set_cache_dir`
+pub(crate) static STORAGE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub(crate) fn get_tmp_dir() -> PathBuf {
+    match STORAGE_DIR.get() {
+        Some(dir) => dir.clone(),
+        None => default_tmp_dir(),
+    }
+}
+
+#[cfg(not(windows))]
+fn default_tmp_dir() -> PathBuf {
+    legacy_tmp_dir()
+}
+
+/// `%LOCALAPPDATA%\live_progress`, an existing `~/.config/live_progress` being moved there on first use.
+#[cfg(windows)]
+fn default_tmp_dir() -> PathBuf {
+    static TMP_DIR: OnceLock<PathBuf> = OnceLock::new();
+    TMP_DIR
+        .get_or_init(|| match local_tmp_dir() {
+            Some(dir) => migrate_tmp_dir(&legacy_tmp_dir(), dir),
+            None => legacy_tmp_dir(),
+        })
+        .clone()
+}
+
+// Resolved through SHGetKnownFolderPath(FOLDERID_LocalAppData)
+#[cfg(windows)]
+fn local_tmp_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("live_progress"))
+}
+
+#[cfg(windows)]
+fn migrate_tmp_dir(legacy: &Path, dir: PathBuf) -> PathBuf {
+    if dir.exists() || !legacy.is_dir() {
+        return dir;
+    }
+    // A failed move, across volumes for instance, leaves the cache where it was
+    match fs::create_dir_all(dir.parent().unwrap_or(dir.as_path()))
+        .and_then(|_| fs::rename(legacy, &dir))
+    {
+        Ok(()) => dir,
+        Err(err) => {
+            tracing::warn!(
+                "could not move {} to {}: {}",
+                legacy.display(),
+                dir.display(),
+                err
+            );
+            legacy.to_path_buf()
         }
-        _ => {
-            if let Ok(hashmap) = fetch() {
-                set_tmp_file_path(&hashmap)?;
-                Ok(hashmap)
-            } else {
-                // Network error, use default expectancy data
-                Ok(receive_default_expectancy()?)
+    }
+}
+
+fn legacy_tmp_dir() -> PathBuf {
+    let home_dir = dirs::home_dir().unwrap_or(PathBuf::from("."));
+
+    home_dir.join(".config").join("live_progress")
+}
+
+pub(crate) fn get_tmp_file_path() -> PathBuf {
+    get_tmp_dir().join(".tmp_expectancy.json")
+}
+
+// Cache files wrap the serialized payload with its digest, so a truncated or edited file is a miss
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    sha256: String,
+    payload: Box<RawValue>,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// `None` when the file is missing, or fails its checksum or `Schema` check, in which case it is quarantined.
+/// Files from before the envelope are migrated in place.
+pub(crate) fn read_tmp_file<T: Schema + Serialize>(path: &Path) -> Result<Option<T>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let json = fs::read(path)?;
+    let content = match serde_json::from_slice::<Envelope>(&json) {
+        Ok(envelope) if sha256_hex(envelope.payload.get().as_bytes()) != envelope.sha256 => {
+            Err("checksum mismatch".to_string())
+        }
+        Ok(envelope) => parse_strict::<T>(envelope.payload.get()),
+        Err(err) => match std::str::from_utf8(&json).map(parse_strict::<T>) {
+            Ok(Ok(content)) => {
+                migrate_tmp_file(path, &content)?;
+                Ok(content)
             }
+            // Neither form, report why it isn't an envelope
+            _ => Err(err.to_string()),
+        },
+    };
+
+    match content {
+        Ok(content) => Ok(Some(content)),
+        Err(reason) => {
+            quarantine(path, &reason)?;
+            Ok(None)
         }
     }
 }
 
-fn ensure_tmp_exist() -> Result<()> {
-    if let Some(tmp_path) = get_tmp_file_path().parent() {
-        fs::create_dir_all(tmp_path)?;
-    }
+// Bare JSON as written by earlier versions, wrapped keeping its modification time so its age is unchanged
+fn migrate_tmp_file<T: Serialize>(path: &Path, content: &T) -> Result<()> {
+    let modified = fs::metadata(path)?.modified()?;
+    write_tmp_file(path, content)?;
+    File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(modified)?;
+    tracing::info!(path = %path.display(), "migrated cache file from the legacy format");
+
     Ok(())
 }
 
-fn get_tmp_file_path() -> PathBuf {
-    let home_dir = dirs::home_dir().or(Some(PathBuf::from("."))).unwrap();
+/// Whether a cache file is present and matches its checksum, without deserializing or quarantining it.
+pub(crate) fn is_intact(path: &Path) -> bool {
+    fs::read(path)
+        .ok()
+        .and_then(|json| serde_json::from_slice::<Envelope>(&json).ok())
+        .is_some_and(|envelope| sha256_hex(envelope.payload.get().as_bytes()) == envelope.sha256)
+}
+
+/// Moves a bad cache file aside as `<name>.corrupt`, kept for inspection while the next fetch rewrites the cache.
+pub(crate) fn quarantine(path: &Path, reason: &str) -> Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".corrupt");
+    tracing::warn!(path = %path.display(), reason, "bad cache file quarantined");
+    fs::rename(path, path.with_file_name(name))?;
 
-    home_dir
-        .join(".config")
-        .join("live_progress")
-        .join(".tmp_expectancy.json")
+    Ok(())
 }
 
-fn set_tmp_file_path(content: &HashMap<String, CountryInfo>) -> Result<()> {
-    ensure_tmp_exist()?;
-    let mut output = File::create(get_tmp_file_path())?;
-    output.write_all(serde_json::to_string(content)?.as_bytes())?;
+pub(crate) fn write_tmp_file<T: Serialize>(path: &Path, content: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let payload = serde_json::to_string(content)?;
+    let envelope = Envelope {
+        sha256: sha256_hex(payload.as_bytes()),
+        payload: RawValue::from_string(payload)?,
+    };
+    let mut output = File::create(path)?;
+    output.write_all(serde_json::to_string(&envelope)?.as_bytes())?;
 
     Ok(())
 }
 
-fn calculate_common(content: &HashMap<String, CountryInfo>) -> CountryInfo {
+pub(crate) fn calculate_common(content: &HashMap<String, CountryInfo>) -> CountryInfo {
     let mut total_all = 0.0;
     let mut total_male = 0.0;
     let mut total_female = 0.0;
     let total = content.len() as f64;
 
-    for (_, info) in content {
+    for info in content.values() {
         total_all += info.all;
         total_male += info.male;
         total_female += info.female
     }
 
+    // Left unrounded, `Precision` takes care of it when the value is returned
     CountryInfo {
-        all: shave_round(total_all / total, None),
-        male: shave_round(total_male / total, None),
-        female: shave_round(total_female / total, None),
+        all: total_all / total,
+        male: total_male / total,
+        female: total_female / total,
     }
 }
 
@@ -88,48 +459,200 @@ pub fn shave_round(num: f64, place: Option<u32>) -> f64 {
     (num * base).round() / base
 }
 
-fn has_cache() -> Result<bool> {
-    if let Ok(metadata) = metadata(get_tmp_file_path()) {
-        return Ok(metadata.is_file());
-    }
+// Same data as `default_expectancy.json`, for clients that must not touch the filesystem
+const EMBEDDED_DEFAULT_EXPECTANCY: &str = include_str!("../default_expectancy.json");
 
-    Ok(false)
+pub(crate) fn embedded_default_expectancy() -> Result<HashMap<String, CountryInfo>> {
+    Ok(serde_json::from_str(EMBEDDED_DEFAULT_EXPECTANCY)?)
 }
 
-fn receive_default_expectancy() -> Result<HashMap<String, CountryInfo>> {
+// The file may have been replaced by a newer release, an unsigned or tampered one is refused
+#[cfg(feature = "defaults-file")]
+pub(crate) fn receive_default_expectancy() -> Result<HashMap<String, CountryInfo>> {
     let default_expectancy_path = env::current_dir()?.join("default_expectancy.json");
-    let json = fs::read_to_string(default_expectancy_path)?;
-    Ok(serde_json::from_str::<HashMap<String, CountryInfo>>(&json)?)
-}
-
-fn fetch() -> Result<HashMap<String, CountryInfo>> {
-    let mut result: HashMap<String, CountryInfo> = HashMap::new();
-    let resp = reqwest::blocking::get(FETCH_URL)?.text()?;
-    let document = Document::from(resp.as_str());
-    if let Some(target_table) = document.find(Class("wikitable")).nth(3) {
-        let tbody = target_table.find(Name("tbody")).next().unwrap();
-        println!("{:?}", tbody);
-
-        for tr in tbody.find(Name("tr")).skip(2) {
-            let mut tds = tr.find(Name("td")).take(4);
-            if let Some(country_name) = extract_country_name(tds.next()) {
-                let all = tds.next().unwrap().text().trim().parse::<f64>()?;
-                let male = tds.next().unwrap().text().trim().parse::<f64>()?;
-                let female = tds.next().unwrap().text().trim().parse::<f64>()?;
-                result.insert(country_name, CountryInfo { all, male, female });
-            }
-        }
+    if !default_expectancy_path.is_file() {
+        return Err(anyhow!("No {}", default_expectancy_path.display()));
+    }
+    let json = signature::read_verified(&default_expectancy_path)?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// Assets of the project's latest release, where `ClientBuilder::release_defaults` downloads
+/// `default_expectancy.json` and its signature from.
+pub const RELEASES_URL: &str =
+    "https://github.com/liwuhou/lifespan-crawler/releases/latest/download";
+
+/// `default_expectancy.json` of `base_url`, along with the `.sig` it must match.
+#[cfg(feature = "blocking")]
+pub(crate) fn download_default_expectancy(base_url: &str) -> Result<HashMap<String, CountryInfo>> {
+    let url = format!("{}/default_expectancy.json", base_url.trim_end_matches('/'));
+    let json = ReqwestTransport.get(&url)?;
+    let signature = ReqwestTransport.get(&format!("{}.sig", url))?;
+    signature::verify(&json, &String::from_utf8_lossy(&signature))
+        .with_context(|| format!("Untrusted {}", url))?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+// The article as of `revision`, its latest one when `None`
+#[cfg(feature = "blocking")]
+pub(crate) fn article_url(revision: Option<u64>) -> String {
+    match revision {
+        Some(revision) => format!("{}?oldid={}", FETCH_URL, revision),
+        None => FETCH_URL.to_string(),
+    }
+}
+
+// Whole body at once, so only the start and the end of the download are reported
+#[cfg(feature = "blocking")]
+pub(crate) fn download_tables_through(
+    transport: &dyn Transport,
+    url: &str,
+    progress: &progress::Progress,
+) -> Result<(String, Provenance)> {
+    progress(Phase::Downloading, 0.0);
+    tracing::debug!(url, "fetching through a custom transport");
+    let body = transport.get(url)?;
+    limits::check_length(Some(body.len() as u64), limits::MAX_PAGE_BYTES, "The page")?;
+    let tables = extract_tables(&body[..])?;
+    progress(Phase::Downloading, 1.0);
+
+    let provenance = Provenance {
+        revision: provenance::revision_in(&body, true),
+        ..Provenance::new(FETCH_URL)
+    };
+    Ok((tables, provenance))
+}
+
+#[cfg(feature = "blocking")]
+pub(crate) fn download_tables(progress: &progress::Progress) -> Result<(String, Provenance)> {
+    download_tables_from(FETCH_URL, None, progress)
+}
+
+#[cfg(feature = "blocking")]
+pub(crate) fn download_tables_from(
+    url: &str,
+    timeout: Option<Duration>,
+    progress: &progress::Progress,
+) -> Result<(String, Provenance)> {
+    progress(Phase::Downloading, 0.0);
+    tracing::debug!(url, "fetching");
+    let resp = rate_limit::get_within(url, timeout)?;
+    let total = resp.content_length();
+    tracing::debug!(status = %resp.status(), bytes = ?total, "response");
+    limits::check_length(total, limits::MAX_PAGE_BYTES, "The page")?;
+    let etag = resp
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string);
+    let body = limits::Limited::new(resp, limits::MAX_PAGE_BYTES, "The page");
+    let mut body = provenance::RevisionReader::new(body);
+    let tables = extract_tables(progress::ProgressReader::new(&mut body, total, progress))?;
+    progress(Phase::Downloading, 1.0);
+
+    let provenance = Provenance {
+        revision: body.revision(),
+        etag,
+        ..Provenance::new(FETCH_URL)
+    };
+    tracing::debug!(revision = ?provenance.revision, etag = ?provenance.etag, "provenance");
+    Ok((tables, provenance))
+}
+
+#[cfg(feature = "replay")]
+pub(crate) fn fetch_html() -> Result<String> {
+    use std::io::Read;
+
+    let resp = rate_limit::get(FETCH_URL)?;
+    limits::check_length(resp.content_length(), limits::MAX_PAGE_BYTES, "The page")?;
+    let mut html = String::new();
+    limits::Limited::new(resp, limits::MAX_PAGE_BYTES, "The page").read_to_string(&mut html)?;
+    Ok(html)
+}
+
+/// Reads a copy of the article obtained some other way, from a mirror, an archive or a test
+/// fixture, the way a fetch reads it. The whole page or only its tables.
+#[cfg(feature = "html")]
+pub fn parse_html(html: &str) -> Result<Dataset> {
+    Ok(Dataset::new(parse_countries(html)?))
+}
+
+/// `parse_html` of a saved page, streamed so a mirrored dump needn't fit in memory.
+#[cfg(feature = "html")]
+pub fn from_html_file(path: impl AsRef<Path>) -> Result<Dataset> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    parse_html(&extract_tables(file)?)
+}
+
+#[cfg(feature = "html")]
+pub(crate) fn parse_countries(html: &str) -> Result<HashMap<String, CountryInfo>> {
+    let document = Html::parse_document(html);
+    let mut result = tables::parse_rows(tables::main_table(&document)?)?;
+    if result.is_empty() {
+        return Err(Error::Parse("no life expectancy table found".to_string()).into());
     }
     // Insert average
-    result.insert(String::from("Common"), calculate_common(&result));
+    result.insert(String::from(COMMON), calculate_common(&result));
 
     Ok(result)
 }
 
-fn extract_country_name(node: Option<Node>) -> Option<String> {
-    if let Some(node) = node {
-        Some(node.find(Name("a")).next().unwrap().text().to_string())
-    } else {
-        None
+#[cfg(feature = "html")]
+// Rows whose link text isn't a name, footnote markers for instance, are skipped
+fn extract_country_name(node: Option<ElementRef>) -> Option<String> {
+    let link = node?.select(&selector("a")).next()?;
+    Some(CountryName::new(&text(link)).ok()?.into_string())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(windows)]
+    use super::*;
+
+    #[cfg(windows)]
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lifespan-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn tmp_dir_is_under_local_app_data() {
+        let local = PathBuf::from(std::env::var_os("LOCALAPPDATA").unwrap());
+        assert_eq!(local_tmp_dir(), Some(local.join("live_progress")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn legacy_tmp_dir_is_moved() {
+        let root = scratch_dir("migrate");
+        let legacy = root.join(".config").join("live_progress");
+        let dir = root.join("AppData").join("Local").join("live_progress");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join(".tmp_expectancy.json"), "{}").unwrap();
+
+        assert_eq!(migrate_tmp_dir(&legacy, dir.clone()), dir);
+        assert!(!legacy.exists());
+        assert_eq!(
+            fs::read_to_string(dir.join(".tmp_expectancy.json")).unwrap(),
+            "{}"
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn existing_tmp_dir_is_kept() {
+        let root = scratch_dir("keep");
+        let legacy = root.join("legacy");
+        let dir = root.join("local");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(migrate_tmp_dir(&legacy, dir.clone()), dir);
+        assert!(legacy.is_dir());
+        fs::remove_dir_all(&root).unwrap();
     }
 }
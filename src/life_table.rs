@@ -0,0 +1,151 @@
+use crate::gho::{fetch_indicator, sex_code};
+use crate::Sex;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+// "nqx - probability of dying between ages x and x+n"
+const NQX_INDICATOR: &str = "LIFE_0000000029";
+const RADIX: f64 = 100_000.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgeGroup {
+    pub start: u32,
+    // None for the open-ended last group (e.g. 85+)
+    pub width: Option<u32>,
+    pub qx: f64,
+    pub lx: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LifeTable {
+    pub country: String,
+    pub sex: Sex,
+    pub year: u32,
+    pub groups: Vec<AgeGroup>,
+}
+
+impl LifeTable {
+    /// Builds a table from `(start, width, qx)` rows, deriving `lx` from a radix of 100 000.
+    pub fn from_qx(
+        country: &str,
+        sex: Sex,
+        year: u32,
+        mut rows: Vec<(u32, Option<u32>, f64)>,
+    ) -> Self {
+        rows.sort_by_key(|(start, _, _)| *start);
+        let mut lx = RADIX;
+        let groups = rows
+            .into_iter()
+            .map(|(start, width, qx)| {
+                let group = AgeGroup {
+                    start,
+                    width,
+                    qx,
+                    lx,
+                };
+                lx *= 1.0 - qx;
+                group
+            })
+            .collect();
+
+        LifeTable {
+            country: country.to_string(),
+            sex,
+            year,
+            groups,
+        }
+    }
+
+    /// Proportion of the birth cohort still alive at the start of each age group.
+    pub fn survival_curve(&self) -> Vec<(u32, f64)> {
+        self.groups
+            .iter()
+            .map(|group| (group.start, group.lx / RADIX))
+            .collect()
+    }
+
+    /// Probability of surviving from birth to `age`.
+    pub fn survival_to(&self, age: f64) -> f64 {
+        if age <= 0.0 {
+            return 1.0;
+        }
+        let mut last_closed: Option<&AgeGroup> = None;
+        for group in &self.groups {
+            let start = group.start as f64;
+            match group.width {
+                Some(width) if age < start + width as f64 => {
+                    // Constant hazard inside the age group
+                    let fraction = (age - start) / width as f64;
+                    return group.lx / RADIX * (1.0 - group.qx).powf(fraction);
+                }
+                Some(_) => last_closed = Some(group),
+                None if age >= start => {
+                    // The open-ended group has qx = 1, extrapolate with the last closed group's hazard
+                    let Some(prev) = last_closed else {
+                        return 0.0;
+                    };
+                    let rate = (1.0 - prev.qx).powf(1.0 / prev.width.unwrap_or(1) as f64);
+                    return group.lx / RADIX * rate.powf(age - start);
+                }
+                None => {}
+            }
+        }
+
+        self.groups
+            .last()
+            .map(|group| group.lx * (1.0 - group.qx) / RADIX)
+            .unwrap_or(0.0)
+    }
+
+    /// Probability that someone alive at `from_age` is still alive at `to_age`.
+    pub fn survival_probability(&self, from_age: f64, to_age: f64) -> f64 {
+        if to_age <= from_age {
+            return 1.0;
+        }
+        let from = self.survival_to(from_age);
+        if from == 0.0 {
+            return 0.0;
+        }
+
+        self.survival_to(to_age) / from
+    }
+}
+
+/// Downloads the latest abridged life table for a country, identified by its ISO 3166-1 alpha-3 code.
+pub fn fetch_life_table(iso3: &str, sex: Sex) -> Result<LifeTable> {
+    let iso3 = iso3.to_uppercase();
+    let filter = format!("SpatialDim eq '{}' and Dim1 eq '{}'", iso3, sex_code(sex));
+    let facts = fetch_indicator(NQX_INDICATOR, Some(filter))?;
+
+    let year = facts
+        .iter()
+        .map(|fact| fact.year)
+        .max()
+        .ok_or_else(|| anyhow!("No life table found for {}", iso3))?;
+    let rows = facts
+        .iter()
+        .filter(|fact| fact.year == year)
+        .filter_map(|fact| {
+            let (start, width) = parse_age_group(fact.age_group.as_deref()?)?;
+            Some((start, width, fact.value?))
+        })
+        .collect();
+
+    Ok(LifeTable::from_qx(&iso3, sex, year, rows))
+}
+
+// "AGEGROUP_AGELT1" | "AGEGROUP_AGE1-4" | "AGEGROUP_AGE85PLUS"
+fn parse_age_group(code: &str) -> Option<(u32, Option<u32>)> {
+    let code = code.strip_prefix("AGEGROUP_AGE")?;
+    if code == "LT1" {
+        return Some((0, Some(1)));
+    }
+    if let Some(start) = code.strip_suffix("PLUS") {
+        return Some((start.parse().ok()?, None));
+    }
+    let (start, end) = code.split_once('-')?;
+    let start = start.parse::<u32>().ok()?;
+    let end = end.parse::<u32>().ok()?;
+
+    Some((start, Some(end + 1 - start)))
+}
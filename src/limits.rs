@@ -0,0 +1,68 @@
+//! Caps on downloaded and decompressed sizes, so a wrong URL or a misbehaving proxy fails with
+//! an error instead of exhausting memory.
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "blocking")]
+use std::io::{self, Read};
+
+/// The life expectancy article is under 1 MB, subnational lists are smaller.
+pub const MAX_PAGE_BYTES: u64 = 16 * 1024 * 1024;
+/// The UN WPP indicators CSV, about 10 MB compressed.
+#[cfg(feature = "cohort")]
+pub const MAX_ARCHIVE_BYTES: u64 = 64 * 1024 * 1024;
+/// Same CSV once decompressed, a larger output is treated as a decompression bomb.
+#[cfg(feature = "cohort")]
+pub const MAX_DECOMPRESSED_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Fails right away when the announced `Content-Length` is over `limit`.
+pub(crate) fn check_length(length: Option<u64>, limit: u64, what: &str) -> Result<()> {
+    match length {
+        Some(length) if length > limit => Err(anyhow!(
+            "{} of {} bytes is over the {} byte limit",
+            what,
+            length,
+            limit
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reader failing with `InvalidData` once more than `limit` bytes came through, where `Read::take` would silently truncate.
+#[cfg(feature = "blocking")]
+pub(crate) struct Limited<R> {
+    inner: R,
+    remaining: u64,
+    limit: u64,
+    what: &'static str,
+}
+
+#[cfg(feature = "blocking")]
+impl<R: Read> Limited<R> {
+    pub(crate) fn new(inner: R, limit: u64, what: &'static str) -> Self {
+        Limited {
+            inner,
+            remaining: limit,
+            limit,
+            what,
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<R: Read> Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // One byte past the limit tells an exact fit from an overflow
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining.saturating_add(1)).unwrap_or(usize::MAX));
+        let read = self.inner.read(&mut buf[..max])?;
+        if read as u64 > self.remaining {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is over the {} byte limit", self.what, self.limit),
+            ));
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::countries::country_by_code;
+use std::env;
+
+/// Best guess of the user's country from the system locale, as a dataset key.
+pub fn default_country() -> Option<&'static str> {
+    system_locales()
+        .iter()
+        .find_map(|locale| country_from_locale(locale))
+}
+
+/// Maps the region part of a locale ("de_DE.UTF-8", "pt-BR", "sr_RS@latin") to a dataset key.
+pub fn country_from_locale(locale: &str) -> Option<&'static str> {
+    let locale = locale.split(['.', '@']).next()?;
+    let region = locale
+        .split(['_', '-'])
+        .skip(1)
+        // Skip script subtags such as "Hans" in "zh-Hans-CN"
+        .find(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic()))?;
+
+    country_by_code(region)
+}
+
+/// Locale used for messages, "zh_CN.UTF-8" for instance, with LC_MESSAGES taking the place of LC_CTYPE.
+pub fn message_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| env::var(key).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+        .or_else(windows_locale)
+}
+
+fn system_locales() -> Vec<String> {
+    // Same precedence as setlocale(3)
+    let mut locales: Vec<String> = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|key| env::var(key).ok())
+        .filter(|value| !value.is_empty() && value != "C" && value != "POSIX")
+        .collect();
+    if let Some(locale) = windows_locale() {
+        locales.push(locale);
+    }
+
+    locales
+}
+
+#[cfg(windows)]
+fn windows_locale() -> Option<String> {
+    use windows_sys::Win32::Globalization::GetUserDefaultLocaleName;
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut buffer = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(buffer.as_mut_ptr(), buffer.len() as i32) };
+    if len <= 1 {
+        return None;
+    }
+
+    // The returned length includes the terminating null
+    String::from_utf16(&buffer[..len as usize - 1]).ok()
+}
+
+#[cfg(not(windows))]
+fn windows_locale() -> Option<String> {
+    None
+}
@@ -0,0 +1,116 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Lookup form of a name: compatibility-decomposed, diacritics and case folded,
+/// apostrophe variants unified and whitespace collapsed.
+///
+/// "Côte d’Ivoire", "COTE D'IVOIRE " and "Côte d'Ivoire" all normalize to "cote d'ivoire".
+pub fn normalize(name: &str) -> String {
+    let folded: String = name
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{02BC}' | '\u{0060}' => '\'',
+            '\u{2010}'..='\u{2015}' => '-',
+            c => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A name cleaned on construction: NFC-composed, trimmed, inner whitespace collapsed to single spaces,
+/// so "Japan " and "Japan" are the same map key. Names without a letter or with control characters are rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CountryName(String);
+
+// Longer than any real country or subdivision name, shorter than a pasted paragraph
+const MAX_NAME_LEN: usize = 100;
+
+impl CountryName {
+    pub fn new(name: &str) -> Result<Self> {
+        let cleaned = name
+            .nfc()
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !cleaned.chars().any(char::is_alphabetic) {
+            return Err(anyhow!("{:?} is not a country name", name));
+        }
+        if cleaned.chars().any(char::is_control) || cleaned.chars().count() > MAX_NAME_LEN {
+            return Err(anyhow!("{:?} is not a country name", name));
+        }
+
+        Ok(CountryName(cleaned))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// Lenient form for fuzzy lookups, see `normalize`.
+    pub fn lookup_key(&self) -> String {
+        normalize(&self.0)
+    }
+}
+
+impl FromStr for CountryName {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        CountryName::new(s)
+    }
+}
+
+impl TryFrom<String> for CountryName {
+    type Error = anyhow::Error;
+
+    fn try_from(name: String) -> Result<Self> {
+        CountryName::new(&name)
+    }
+}
+
+impl From<CountryName> for String {
+    fn from(name: CountryName) -> Self {
+        name.0
+    }
+}
+
+impl fmt::Display for CountryName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for CountryName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for CountryName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for CountryName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
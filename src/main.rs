@@ -0,0 +1,1070 @@
+mod config;
+mod filter;
+mod i18n;
+
+use anyhow::{anyhow, Result};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use filter::Filter;
+use i18n::t;
+use lifespan_crawler::cache::{cache_dir, cache_files, clear_cache, set_cache_dir};
+use lifespan_crawler::chart::{bar_chart, sparkline};
+use lifespan_crawler::countries::country_by_code;
+use lifespan_crawler::doctor::{self, Status};
+#[cfg(feature = "geoip")]
+use lifespan_crawler::geoip;
+use lifespan_crawler::health;
+use lifespan_crawler::history::{get_history, Series};
+use lifespan_crawler::lookup::normalize;
+use lifespan_crawler::names::localized_name;
+#[cfg(feature = "notify")]
+use lifespan_crawler::notify::DesktopNotifier;
+use lifespan_crawler::notify::{payload, CommandNotifier, Notifier, WebhookNotifier};
+#[cfg(feature = "scripting")]
+use lifespan_crawler::RhaiScript;
+#[cfg(feature = "wasm-plugins")]
+use lifespan_crawler::WasmPlugin;
+use lifespan_crawler::{
+    error_kind, from_html_file, tagged, untagged, Attribution, CacheStore, Client, ClientBuilder,
+    CountryInfo, Cron, DataDiff, Dataset, Error, ErrorKind, FallbackPolicy, FileCache, JsonOptions,
+    Origin, Query, Schedule, Sex, SqlDialect, RELEASES_URL,
+};
+use lifespan_crawler::{provenance, usage};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+#[cfg(feature = "geoip")]
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tracing::{error, warn};
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser)]
+#[command(
+    name = "lifespan",
+    version,
+    about = "Life expectancy by country",
+    after_help = "Exit codes: 1 other error, 2 usage, 3 network, 4 unparsable page, 5 unknown country, 6 only stale data (--no-defaults), 7 rate limited"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// More logging on stderr, -v for info, -vv for debug, -vvv for trace
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Errors only
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+    /// Structured output on stdout, for show, rank, stats, compare, chart, history, doctor, cache info and watch,
+    /// each document wrapped as `{"$schema", "version", "data"}`
+    #[arg(long, global = true)]
+    json: bool,
+    /// jq-style expression applied to JSON output, `.data.countries[] | select(.value > 80) | .country`
+    /// for instance, each result printed on its own
+    #[arg(long, global = true)]
+    filter: Option<Filter>,
+    /// Errors as a tagged `{"kind", "message"}` line on stderr
+    #[arg(long, global = true)]
+    json_errors: bool,
+    /// Fail when offline with nothing cached, instead of answering from the bundled defaults
+    #[arg(long, global = true)]
+    no_defaults: bool,
+    /// Once a fetch fails: "fail-fast", "prefer-stale-cache" or "allow-defaults", the default
+    #[arg(long, global = true, conflicts_with = "no_defaults")]
+    fallback_policy: Option<FallbackPolicy>,
+    /// Download the signed defaults of the latest release, or of the given mirror, when offline
+    /// without a local `default_expectancy.json`
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = RELEASES_URL, value_name = "URL")]
+    download_defaults: Option<String>,
+    /// Layers tried in order, of "cache", "network", "bundled-file" and "embedded", all four by default
+    #[arg(long, global = true, value_delimiter = ',')]
+    fallback: Vec<Origin>,
+    /// Leave dependencies and other territories out, "Common" averaging the countries only
+    #[arg(long, global = true)]
+    countries_only: bool,
+    /// Check Wikipedia's robots.txt before downloading the article, honoring its Crawl-delay
+    #[arg(long, global = true)]
+    respect_robots: bool,
+    /// Directory for every cache file instead of the per-user default
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+    /// Message language, "en" or "zh", taken from the locale when omitted
+    #[arg(long, global = true)]
+    lang: Option<String>,
+    /// WebAssembly module transforming fetched data before it is cached, repeatable, run in order
+    #[cfg(feature = "wasm-plugins")]
+    #[arg(long, global = true)]
+    plugin: Vec<PathBuf>,
+    /// Rhai script run over each fetched entry before it is cached, seeing and changing `name`,
+    /// `all`, `male` and `female`, the entry dropped when it evaluates to false. Overrides the
+    /// `script` of the config file
+    #[cfg(feature = "scripting")]
+    #[arg(long, global = true)]
+    script: Option<PathBuf>,
+    /// Config file read instead of `config.toml` in the per-user config directory
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Country `show` and `history` use when none is given, asked of a GeoIP service for this
+    /// machine's address, which the service therefore learns. `--detect-country=URL` picks a service
+    /// answering a plain ISO code or JSON with a `country` field, https://api.country.is/ otherwise
+    #[cfg(feature = "geoip")]
+    #[arg(
+        long,
+        global = true,
+        value_name = "URL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = geoip::DEFAULT_SERVICE_URL
+    )]
+    detect_country: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect and manage the cached data
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Bar chart of the countries with the highest life expectancy
+    Chart {
+        #[arg(long, default_value_t = 15)]
+        top: usize,
+        #[arg(long, default_value_t = Sex::All)]
+        sex: Sex,
+        #[arg(long, default_value_t = 40)]
+        width: usize,
+    },
+    /// Matrix of differences between countries, row minus column
+    Compare {
+        #[arg(required = true, num_args = 2..)]
+        countries: Vec<String>,
+        #[arg(long, default_value_t = Sex::All)]
+        sex: Sex,
+        /// CSV instead of an aligned table
+        #[arg(long)]
+        csv: bool,
+    },
+    /// Check the network, the parser and the cache, suggesting fixes
+    Doctor,
+    /// Dataset in a machine-readable format
+    Export {
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// Only the highest entries, for markdown
+        #[arg(long)]
+        top: Option<usize>,
+        /// Tera template rendered instead, given `countries` ranked by overall value, `by_name`,
+        /// `common` and `attribution`
+        #[arg(long, conflicts_with = "format")]
+        template: Option<PathBuf>,
+        /// Boundaries FeatureCollection keyed by ISO code, for geojson
+        #[arg(long)]
+        boundaries: Option<PathBuf>,
+        /// Written to stdout when omitted
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Dataset of a saved copy of the article, read the way a fetch reads it, never touching
+    /// the cache or the network
+    Parse {
+        file: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// Only the highest entries, for markdown
+        #[arg(long)]
+        top: Option<usize>,
+        /// Boundaries FeatureCollection keyed by ISO code, for geojson
+        #[arg(long)]
+        boundaries: Option<PathBuf>,
+        /// Written to stdout when omitted
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Single-file HTML report with a bar chart and a sortable table
+    Report {
+        #[arg(long, default_value = "report.html")]
+        out: PathBuf,
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// Keep the cache fresh, refreshing once after each yearly publication window
+    Watch {
+        /// Hours between checks
+        #[arg(long, default_value_t = 6)]
+        interval: u64,
+        /// Refresh at the times of a cron expression instead, `0 4 1 * *` for 04:00 UTC on the
+        /// first of each month
+        #[arg(long, conflicts_with = "interval")]
+        cron: Option<Cron>,
+        /// Address answering `/healthz` and `/readyz` probes, `0.0.0.0:8080` for instance
+        #[arg(long)]
+        health: Option<SocketAddr>,
+        /// Desktop notification summarizing what changed after each refresh
+        #[cfg(feature = "notify")]
+        #[arg(long)]
+        notify: bool,
+        /// URL receiving a JSON diff whenever a refresh changes the data
+        #[arg(long)]
+        webhook: Option<String>,
+        /// Program run with the JSON diff on stdin whenever a refresh changes the data
+        #[arg(long)]
+        exec: Option<String>,
+    },
+    /// Values of a single country, looked up by name or ISO code
+    Show { country: Option<String> },
+    /// Countries matching a query, `sex=female AND all>80 SORT BY gap DESC LIMIT 10` for instance
+    Query {
+        /// Conditions joined with AND on all, male, female, gap, ratio, sex and region, then
+        /// optional SORT BY <field> [ASC|DESC] and LIMIT <n>
+        query: String,
+    },
+    /// Countries from the highest to the lowest life expectancy
+    Rank {
+        #[arg(long, default_value_t = Sex::All)]
+        sex: Sex,
+        #[arg(long)]
+        top: Option<usize>,
+    },
+    /// Mean, median, spread and extremes over every country
+    Stats {
+        #[arg(long, default_value_t = Sex::All)]
+        sex: Sex,
+    },
+    /// Yearly life expectancy of a country
+    History {
+        country: Option<String>,
+        #[arg(long)]
+        chart: bool,
+        #[arg(long, default_value_t = Sex::All)]
+        sex: Sex,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Directory holding the cache files
+    Path,
+    /// Age, size, source and checksum status of each cache file
+    Info,
+    /// Remove every cache file, snapshots included
+    Clear,
+    /// Cached dataset as plain JSON
+    Export {
+        /// Written to stdout when omitted
+        out: Option<PathBuf>,
+    },
+    /// Replace the cached dataset with a JSON file, as written by `cache export`
+    Import { file: PathBuf },
+    /// Count fetches, cache hits and fallbacks locally, shown by `cache info`
+    Counters {
+        #[arg(value_enum)]
+        action: CountersAction,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CountersAction {
+    On,
+    Off,
+    /// Back to zero, keeping counting on
+    Reset,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// The countries of `--boundaries` with their values, for choropleth maps
+    Geojson,
+    Json,
+    Markdown,
+    Ndjson,
+    /// SQL dump for PostgreSQL
+    Postgres,
+    /// SQL dump for SQLite
+    Sqlite,
+    Toml,
+    #[cfg(feature = "xlsx")]
+    Xlsx,
+    Xml,
+    Yaml,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    i18n::init(cli.lang.as_deref());
+    filter::init(cli.filter);
+    init_logging(cli.verbose, cli.quiet, cli.log_format);
+    #[cfg(feature = "geoip")]
+    if let Some(url) = cli.detect_country {
+        GEOIP_SERVICE
+            .set(url)
+            .expect("the GeoIP service is set once");
+    }
+    if let Some(dir) = cli.cache_dir {
+        // Nothing has touched the cache yet, so this cannot fail
+        set_cache_dir(dir).expect("the cache directory is set once");
+    }
+    let mut builder = Client::builder();
+    if cli.no_defaults {
+        builder = builder.no_defaults();
+    }
+    if let Some(policy) = cli.fallback_policy {
+        builder = builder.fallback_policy(policy);
+    }
+    if cli.countries_only {
+        builder = builder.countries_only();
+    }
+    if cli.respect_robots {
+        builder = builder.respect_robots();
+    }
+    if let Some(url) = cli.download_defaults {
+        builder = builder.defaults_mirror(url);
+    }
+    if !cli.fallback.is_empty() {
+        builder = builder.fallback(cli.fallback);
+    }
+    let builder: Result<ClientBuilder> = Ok(builder);
+    #[cfg(feature = "wasm-plugins")]
+    let builder = builder.and_then(|builder| with_plugins(builder, &cli.plugin));
+    let builder = builder.and_then(|builder| {
+        let config = config::load(cli.config.as_deref())?;
+        #[cfg(feature = "scripting")]
+        let script = cli.script.or(config.script);
+        #[cfg(not(feature = "scripting"))]
+        let script = config.script;
+        with_script(builder, script)
+    });
+
+    match builder.and_then(|builder| run(cli.command, &builder.build(), cli.json)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let kind = error_kind(&err);
+            if cli.json_errors {
+                let error = json!({ "kind": kind, "message": format!("{:#}", err) });
+                match tagged("error", error) {
+                    Ok(error) => eprintln!("{}", error),
+                    Err(_) => eprintln!("{:#}", err),
+                }
+            } else {
+                eprintln!("{}", t!("error", message = format!("{:#}", err)));
+            }
+            ExitCode::from(exit_code(kind))
+        }
+    }
+}
+
+// Compiled before any command runs, so a broken module fails early
+#[cfg(feature = "wasm-plugins")]
+fn with_plugins(mut builder: ClientBuilder, paths: &[PathBuf]) -> Result<ClientBuilder> {
+    for path in paths {
+        builder = builder.transform(WasmPlugin::load(path)?);
+    }
+    Ok(builder)
+}
+
+#[cfg(feature = "scripting")]
+fn with_script(builder: ClientBuilder, script: Option<PathBuf>) -> Result<ClientBuilder> {
+    match script {
+        Some(path) => Ok(builder.transform(RhaiScript::load(path)?)),
+        None => Ok(builder),
+    }
+}
+
+// The config file may be shared with a build that has scripting
+#[cfg(not(feature = "scripting"))]
+fn with_script(builder: ClientBuilder, script: Option<PathBuf>) -> Result<ClientBuilder> {
+    match script {
+        Some(path) => Err(anyhow!(t!("config.no_scripting", path = path.display()))),
+        None => Ok(builder),
+    }
+}
+
+// Service `--detect-country` asks, only set when given
+#[cfg(feature = "geoip")]
+static GEOIP_SERVICE: OnceLock<String> = OnceLock::new();
+
+// Looked up only once a command lacks its country, so other commands send nothing
+fn country_or_detected(country: Option<String>) -> Result<String> {
+    if let Some(country) = country {
+        return Ok(country);
+    }
+    #[cfg(feature = "geoip")]
+    if let Some(url) = GEOIP_SERVICE.get() {
+        let country = geoip::country_from_service(url)?
+            .ok_or_else(|| anyhow!(t!("country.undetected", url = url)))?;
+        tracing::info!("detected country {}", country);
+        return Ok(country.to_string());
+    }
+
+    Err(anyhow!(t!("country.missing")))
+}
+
+// Listed in the `--help` footer, 2 is taken by clap for usage errors
+fn exit_code(kind: ErrorKind) -> u8 {
+    match kind {
+        ErrorKind::Network => 3,
+        ErrorKind::Parse => 4,
+        ErrorKind::UnknownCountry => 5,
+        ErrorKind::StaleOnly => 6,
+        ErrorKind::RateLimited => 7,
+        _ => 1,
+    }
+}
+
+fn run(command: Command, client: &Client, json: bool) -> Result<()> {
+    match command {
+        Command::Cache { action } => cache(action, json),
+        Command::Chart { top, sex, width } => chart(client, top, sex, width, json),
+        Command::Compare {
+            countries,
+            sex,
+            csv,
+        } => compare(client, &countries, sex, csv, json),
+        Command::Doctor => doctor(json),
+        Command::Export {
+            format,
+            top,
+            template,
+            boundaries,
+            out,
+        } => match template {
+            Some(template) => render(client, &template, out),
+            None => export(client, format, top, boundaries, out),
+        },
+        Command::Parse {
+            file,
+            format,
+            top,
+            boundaries,
+            out,
+        } => write_dataset(&from_html_file(file)?, format, top, boundaries, out),
+        Command::Report { out, top } => report(client, &out, top),
+        Command::Watch {
+            interval,
+            cron,
+            health,
+            #[cfg(feature = "notify")]
+            notify,
+            webhook,
+            exec,
+        } => {
+            let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+            #[cfg(feature = "notify")]
+            if notify {
+                notifiers.push(Box::new(DesktopNotifier));
+            }
+            if let Some(url) = webhook {
+                notifiers.push(Box::new(WebhookNotifier::new(url)));
+            }
+            if let Some(program) = exec {
+                notifiers.push(Box::new(CommandNotifier::new(program)));
+            }
+            let Some(addr) = health else {
+                return watch(client, interval, cron, &notifiers, json);
+            };
+            let listener = TcpListener::bind(addr)?;
+            thread::scope(|scope| {
+                scope.spawn(|| {
+                    if let Err(err) = health::serve(&listener, client) {
+                        error!("health endpoint stopped: {:#}", err);
+                    }
+                });
+                watch(client, interval, cron, &notifiers, json)
+            })
+        }
+        Command::Show { country } => show(client, &country_or_detected(country)?, json),
+        Command::Query { query } => run_query(client, &query, json),
+        Command::Rank { sex, top } => rank(client, sex, top, json),
+        Command::Stats { sex } => stats(client, sex, json),
+        Command::History {
+            country,
+            chart,
+            sex,
+        } => history(&country_or_detected(country)?, chart, sex, json),
+    }
+}
+
+// RUST_LOG, when set, takes precedence over the flags
+fn init_logging(verbose: u8, quiet: bool, format: LogFormat) {
+    let level = match (quiet, verbose) {
+        (true, _) => "error",
+        (false, 0) => "warn",
+        (false, 1) => "info",
+        (false, 2) => "debug",
+        _ => "trace",
+    };
+    // Dependencies stay at warn, their debug output drowns ours
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        EnvFilter::new(format!(
+            "{},lifespan={level},lifespan_crawler={level}",
+            if quiet { "error" } else { "warn" }
+        ))
+    });
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal());
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+fn cache(action: CacheAction, json: bool) -> Result<()> {
+    let store = FileCache::default();
+    match action {
+        CacheAction::Path if json => print_json("cache_path", json!({ "path": cache_dir() }))?,
+        CacheAction::Path => println!("{}", cache_dir().display()),
+        CacheAction::Info if json => print_json(
+            "cache_info",
+            json!({
+                "files": cache_files()?,
+                "provenance": provenance::load()?,
+                "usage": usage::counters()?,
+            }),
+        )?,
+        CacheAction::Info => {
+            let dir = cache_dir();
+            let files = cache_files()?;
+            if files.is_empty() {
+                println!("{}", t!("cache.empty", dir = dir.display()));
+            }
+            for file in files {
+                let age = file
+                    .modified
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                    .map(format_age)
+                    .unwrap_or_else(|| "?".to_string());
+                let status = match file.intact {
+                    Some(true) => t!("cache.ok"),
+                    Some(false) => t!("cache.corrupt"),
+                    None => "-".to_string(),
+                };
+                println!(
+                    "{:<32} {:>9} {:>5} {:<8} {}",
+                    file.path.strip_prefix(&dir).unwrap_or(&file.path).display(),
+                    file.size,
+                    age,
+                    status,
+                    file.source
+                );
+            }
+            if let Some(provenance) = provenance::load()? {
+                println!();
+                println!(
+                    "{}",
+                    t!(
+                        "cache.provenance",
+                        url = provenance.permalink(),
+                        date = provenance.retrieved_on()
+                    )
+                );
+            }
+            if let Some(counters) = usage::counters()? {
+                println!();
+                println!(
+                    "{}",
+                    t!(
+                        "cache.usage",
+                        since = counters.since,
+                        fetches = counters.fetches,
+                        failures = counters.fetch_failures,
+                        hits = counters.cache_hits,
+                        misses = counters.cache_misses,
+                        fallbacks = counters.fallbacks
+                    )
+                );
+            }
+        }
+        CacheAction::Counters { action } => match action {
+            CountersAction::On if usage::is_enabled() => println!("{}", t!("cache.counters_on")),
+            CountersAction::On | CountersAction::Reset => {
+                usage::enable()?;
+                println!("{}", t!("cache.counters_on"));
+            }
+            CountersAction::Off => {
+                usage::disable()?;
+                println!("{}", t!("cache.counters_off"));
+            }
+        },
+        CacheAction::Clear => println!("{}", t!("cache.cleared", count = clear_cache()?)),
+        CacheAction::Export { out } => {
+            let data = store
+                .load()?
+                .ok_or_else(|| anyhow!(t!("cache.nothing_to_export")))?;
+            let attribution = Attribution::wikipedia(provenance::load()?.as_ref());
+            let output = Dataset::new(data).with_attribution(attribution);
+            let output = pretty_json(output.to_tagged_json(&JsonOptions::default())?)?;
+            match out {
+                Some(path) => fs::write(path, output)?,
+                None => print!("{}", output),
+            }
+        }
+        CacheAction::Import { file } => {
+            // Files exported before the format was tagged are accepted as they are
+            let document = serde_json::from_str(&fs::read_to_string(&file)?)
+                .map_err(anyhow::Error::from)
+                .and_then(|document| untagged(document, "dataset"));
+            let data: HashMap<String, CountryInfo> = document
+                .and_then(|data| Ok(serde_json::from_value(data)?))
+                .map_err(|err| {
+                    anyhow!(t!(
+                        "cache.not_a_dataset",
+                        file = file.display(),
+                        error = format!("{:#}", err)
+                    ))
+                })?;
+            if data.is_empty() {
+                return Err(anyhow!(t!("cache.no_countries", file = file.display())));
+            }
+            store.store(&data)?;
+            let path = store.path().display();
+            println!("{}", t!("cache.imported", count = data.len(), path = path));
+        }
+    }
+
+    Ok(())
+}
+
+// Text output only, JSON keeps the dataset keys
+fn display_name(country: &str) -> &str {
+    localized_name(country, i18n::language()).unwrap_or(country)
+}
+
+fn sex_label(sex: Sex) -> String {
+    match sex {
+        Sex::All => t!("sex.all"),
+        Sex::Male => t!("sex.male"),
+        Sex::Female => t!("sex.female"),
+    }
+}
+
+// Pretty-printed and tagged, one document per command
+fn print_json(kind: &str, value: impl Serialize) -> Result<()> {
+    print!("{}", pretty_json(tagged(kind, value)?)?);
+    Ok(())
+}
+
+// Every result of `--filter`, one document per line when there is none
+fn pretty_json(value: serde_json::Value) -> Result<String> {
+    let mut output = String::new();
+    for value in filter::apply(value) {
+        output += &(serde_json::to_string_pretty(&value)? + "\n");
+    }
+    Ok(output)
+}
+
+fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        secs if secs < 3600 => format!("{}m", secs / 60),
+        secs if secs < 86_400 => format!("{}h", secs / 3600),
+        secs => format!("{}d", secs / 86_400),
+    }
+}
+
+fn chart(client: &Client, top: usize, sex: Sex, width: usize, json: bool) -> Result<()> {
+    if json {
+        return rank(client, sex, Some(top), json);
+    }
+    let rows: Vec<(String, f64)> = Query::new()
+        .sex(sex)
+        .sort_desc()
+        .limit(top)
+        .run(&client.dataset()?)
+        .into_iter()
+        .map(|row| (display_name(&row.country).to_string(), row.value))
+        .collect();
+    print!("{}", bar_chart(&rows, width));
+
+    Ok(())
+}
+
+fn compare(client: &Client, countries: &[String], sex: Sex, csv: bool, json: bool) -> Result<()> {
+    let countries: Vec<&str> = countries.iter().map(String::as_str).collect();
+    let matrix = client.dataset()?.comparison_matrix(&countries, sex)?;
+    if json {
+        return print_json("comparison", &matrix);
+    }
+    if csv {
+        print!("{}", matrix.to_csv());
+        return Ok(());
+    }
+
+    let width = matrix
+        .countries
+        .iter()
+        .map(|country| country.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    print!("{:width$}", "", width = width);
+    for country in &matrix.countries {
+        print!(" {:>width$}", country, width = width);
+    }
+    println!();
+    for (country, row) in matrix.countries.iter().zip(&matrix.values) {
+        print!("{:width$}", country, width = width);
+        for value in row {
+            print!(" {:>+width$.2}", value, width = width);
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn doctor(json: bool) -> Result<()> {
+    let checks = doctor::run();
+    if json {
+        print_json("doctor", &checks)?;
+    } else {
+        for check in &checks {
+            let status = match check.status {
+                Status::Ok => t!("doctor.ok"),
+                Status::Warning => t!("doctor.warning"),
+                Status::Failed => t!("doctor.failed"),
+            };
+            println!("[{:<4}] {:<9} {}", status, check.name, check.detail);
+            if let Some(fix) = &check.fix {
+                println!("{:17}{}", "", fix);
+            }
+        }
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == Status::Failed)
+        .count();
+    if failed > 0 {
+        return Err(anyhow!(t!("doctor.summary", count = failed)));
+    }
+    Ok(())
+}
+
+fn export(
+    client: &Client,
+    format: Format,
+    top: Option<usize>,
+    boundaries: Option<PathBuf>,
+    out: Option<PathBuf>,
+) -> Result<()> {
+    let dataset = client.dataset()?;
+    // With a sheet per year, unlike a parsed page
+    #[cfg(feature = "xlsx")]
+    if let (Format::Xlsx, Some(out)) = (format, &out) {
+        match get_history() {
+            Ok(history) => return dataset.to_xlsx_with_history(&history, out),
+            Err(err) => warn!("history unavailable, writing the snapshot only: {:#}", err),
+        }
+    }
+    write_dataset(&dataset, format, top, boundaries, out)
+}
+
+fn write_dataset(
+    dataset: &Dataset,
+    format: Format,
+    top: Option<usize>,
+    boundaries: Option<PathBuf>,
+    out: Option<PathBuf>,
+) -> Result<()> {
+    let output = match format {
+        Format::Geojson => {
+            let boundaries =
+                boundaries.ok_or_else(|| anyhow!(t!("export.geojson_needs_boundaries")))?;
+            pretty_json(dataset.to_geojson_file(&boundaries)?)?
+        }
+        Format::Json => pretty_json(dataset.to_tagged_json(&JsonOptions::default())?)?,
+        Format::Markdown => dataset.to_markdown(top),
+        Format::Ndjson => dataset.to_ndjson()?,
+        Format::Postgres => dataset.export_sql(SqlDialect::Postgres),
+        Format::Sqlite => dataset.export_sql(SqlDialect::Sqlite),
+        Format::Toml => dataset.to_toml()?,
+        #[cfg(feature = "xlsx")]
+        Format::Xlsx => {
+            // Binary, so it only goes to a file
+            let out = out.ok_or_else(|| anyhow!(t!("export.xlsx_needs_out")))?;
+            return dataset.to_xlsx(&out);
+        }
+        Format::Xml => dataset.to_xml(),
+        Format::Yaml => dataset.to_yaml()?,
+    };
+    match out {
+        Some(path) => fs::write(path, output)?,
+        None => print!("{}", output),
+    }
+
+    Ok(())
+}
+
+fn render(client: &Client, template: &Path, out: Option<PathBuf>) -> Result<()> {
+    let output = client
+        .dataset()?
+        .render_template(&fs::read_to_string(template)?)?;
+    match out {
+        Some(path) => fs::write(path, output)?,
+        None => print!("{}", output),
+    }
+
+    Ok(())
+}
+
+fn report(client: &Client, out: &Path, top: usize) -> Result<()> {
+    fs::write(out, client.dataset()?.to_html_report(top))?;
+    println!("{}", t!("report.written", path = out.display()));
+
+    Ok(())
+}
+
+fn show(client: &Client, country: &str, json: bool) -> Result<()> {
+    let dataset = client.dataset()?;
+    let (name, info) = dataset
+        .get_key_value(country)
+        .or_else(|| dataset.get_key_value(country_by_code(country)?))
+        .ok_or_else(|| Error::UnknownCountry(country.to_string()))?;
+    if json {
+        return print_json(
+            "country",
+            json!({
+            "country": name,
+            "all": info.all,
+            "male": info.male,
+            "female": info.female,
+            }),
+        );
+    }
+
+    println!("{}", display_name(name));
+    for sex in [Sex::All, Sex::Male, Sex::Female] {
+        let percentile = dataset.percentile_of(name, sex).unwrap_or_default();
+        let percentile = t!("show.percentile", value = format!("{:.0}", percentile));
+        println!(
+            "  {:<7} {:>6.2}  ({})",
+            sex_label(sex),
+            info.get(sex),
+            percentile
+        );
+    }
+
+    Ok(())
+}
+
+fn rank(client: &Client, sex: Sex, top: Option<usize>, json: bool) -> Result<()> {
+    let mut query = Query::new().sex(sex).sort_desc();
+    if let Some(top) = top {
+        query = query.limit(top);
+    }
+    let ranked = query.run(&client.dataset()?).into_iter().enumerate();
+    if json {
+        let rows: Vec<_> = ranked
+            .map(|(index, row)| json!({ "rank": index + 1, "country": row.country, "value": row.value }))
+            .collect();
+        return print_json("rank", json!({ "sex": sex, "countries": rows }));
+    }
+
+    for (index, row) in ranked {
+        println!(
+            "{:>4} {:<40} {:>6.2}",
+            index + 1,
+            display_name(&row.country),
+            row.value
+        );
+    }
+
+    Ok(())
+}
+
+fn run_query(client: &Client, text: &str, json: bool) -> Result<()> {
+    let query: Query = text.parse()?;
+    let rows = query.run(&client.dataset()?);
+    if json {
+        return print_json("query", json!({ "query": text, "countries": rows }));
+    }
+
+    let key = query.key();
+    for (index, row) in rows.iter().enumerate() {
+        let value = key
+            .of(&row.info)
+            .map_or_else(|| "-".to_string(), |value| format!("{:.2}", value));
+        println!(
+            "{:>4} {:<40} {:>6}",
+            index + 1,
+            display_name(&row.country),
+            value
+        );
+    }
+
+    Ok(())
+}
+
+fn stats(client: &Client, sex: Sex, json: bool) -> Result<()> {
+    let summary = client
+        .dataset()?
+        .summary(sex)
+        .ok_or_else(|| anyhow!(t!("stats.empty")))?;
+    if json {
+        return print_json("stats", &summary);
+    }
+
+    println!("{:<9} {:>8}", t!("stats.countries"), summary.count);
+    println!("{:<9} {:>8.2}", t!("stats.mean"), summary.mean);
+    println!("{:<9} {:>8.2}", t!("stats.median"), summary.median);
+    println!("{:<9} {:>8.2}", t!("stats.std_dev"), summary.std_dev);
+    let (min, max) = (&summary.min, &summary.max);
+    println!(
+        "{:<9} {:>8.2}  {}",
+        t!("stats.min"),
+        min.1,
+        display_name(&min.0)
+    );
+    println!(
+        "{:<9} {:>8.2}  {}",
+        t!("stats.max"),
+        max.1,
+        display_name(&max.0)
+    );
+
+    Ok(())
+}
+
+// Waits between attempts at filling an empty cache under `watch --cron`
+const FILL_RETRY_MIN: Duration = Duration::from_secs(60);
+const FILL_RETRY_MAX: Duration = Duration::from_secs(3600);
+
+fn watch(
+    client: &Client,
+    interval: u64,
+    cron: Option<Cron>,
+    notifiers: &[Box<dyn Notifier>],
+    json: bool,
+) -> Result<()> {
+    let schedule = Schedule::default();
+    // An empty cache is filled right away rather than at the next time the cron gives, failed
+    // attempts backing off up to that time
+    let mut filling = client.last_updated().ok().flatten().is_none();
+    let mut backoff: Option<Duration> = None;
+    loop {
+        if let Some(cron) = &cron {
+            let now = SystemTime::now();
+            let next = cron
+                .next_after(now)
+                .ok_or_else(|| anyhow!(t!("watch.never", cron = cron)))?;
+            let until_next = next.duration_since(now).unwrap_or_default();
+            match (filling, backoff) {
+                (true, None) => {}
+                (true, Some(backoff)) => thread::sleep(backoff.min(until_next)),
+                (false, _) => thread::sleep(until_next),
+            }
+        }
+        let before = client.cached().ok().flatten().unwrap_or_default();
+        // A failed refresh stays due, so it is retried on the next check. The cron says when
+        // to refresh by itself.
+        let refreshed = match cron {
+            Some(_) => client.refresh().map(Some),
+            None => client.refresh_if_due(&schedule),
+        };
+        let refreshed_ok = refreshed.is_ok();
+        match refreshed {
+            Ok(Some(after)) => {
+                let diff = DataDiff::between(&before, &after);
+                if json {
+                    // One line per refresh, so the stream can be read as NDJSON
+                    for value in filter::apply(payload(&diff)?) {
+                        println!("{}", serde_json::to_string(&value)?);
+                    }
+                } else {
+                    println!("{}", t!("watch.refreshed", summary = diff.summary()));
+                }
+                if !diff.is_empty() {
+                    for notifier in notifiers {
+                        if let Err(err) = notifier.notify(&diff) {
+                            warn!("notification failed: {:#}", err);
+                        }
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                error!("refresh failed: {:#}", err);
+                if filling {
+                    backoff = Some(
+                        backoff.map_or(FILL_RETRY_MIN, |backoff| (backoff * 2).min(FILL_RETRY_MAX)),
+                    );
+                }
+            }
+        }
+        if refreshed_ok {
+            filling = false;
+        }
+        if cron.is_none() {
+            thread::sleep(Duration::from_secs(interval.max(1) * 3600));
+        }
+    }
+}
+
+// Looked up like `show`: by name, then ISO code, then ignoring case, diacritics and spacing
+fn find_series<'a>(
+    history: &'a HashMap<String, Series>,
+    country: &str,
+) -> Option<(&'a String, &'a Series)> {
+    let name = match history.get_key_value(country) {
+        Some(entry) => return Some(entry),
+        None => country_by_code(country).unwrap_or(country),
+    };
+    let wanted = normalize(name);
+    history
+        .get_key_value(name)
+        .or_else(|| history.iter().find(|(key, _)| normalize(key) == wanted))
+}
+
+fn history(country: &str, chart: bool, sex: Sex, json: bool) -> Result<()> {
+    let history = get_history()?;
+    let (country, series) =
+        find_series(&history, country).ok_or_else(|| Error::UnknownCountry(country.to_string()))?;
+    if json {
+        return print_json("history", json!({ "country": country, "series": series }));
+    }
+
+    if chart {
+        let values: Vec<f64> = series.values().map(|info| info.get(sex)).collect();
+        if let (Some((first, _)), Some((last, _))) =
+            (series.first_key_value(), series.last_key_value())
+        {
+            println!("{} {} {}", first, sparkline(&values), last);
+        }
+        let rows: Vec<(String, f64)> = series
+            .iter()
+            .map(|(year, info)| (year.to_string(), info.get(sex)))
+            .collect();
+        print!("{}", bar_chart(&rows, 40));
+    } else {
+        println!(
+            "{:<6} {:>7} {:>7} {:>7}",
+            t!("history.year"),
+            sex_label(Sex::All),
+            sex_label(Sex::Male),
+            sex_label(Sex::Female)
+        );
+        for (year, info) in series {
+            println!(
+                "{:<6} {:>7.2} {:>7.2} {:>7.2}",
+                year, info.all, info.male, info.female
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use crate::countries::country_by_code;
+use crate::lookup::normalize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const COUNTRY_NAMES_JSON: &str = include_str!("../country_names.json");
+
+/// Languages with bundled display names, English being the dataset keys themselves.
+pub const LANGUAGES: &[&str] = &["en", "de", "es", "fr", "zh"];
+
+fn names() -> &'static HashMap<String, HashMap<String, String>> {
+    static NAMES: OnceLock<HashMap<String, HashMap<String, String>>> = OnceLock::new();
+    NAMES.get_or_init(|| {
+        serde_json::from_str(COUNTRY_NAMES_JSON).expect("bundled country_names.json is valid")
+    })
+}
+
+// "de", "de-DE", "de_DE.UTF-8", "zh-Hans" → "de" / "zh"
+fn primary_language(lang: &str) -> String {
+    lang.split(['-', '_', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Display name of a dataset entry in `lang`.
+pub fn localized_name(country: &str, lang: &str) -> Option<&'static str> {
+    let (key, translations) = names().get_key_value(country)?;
+    match primary_language(lang).as_str() {
+        "en" => Some(key.as_str()),
+        lang => translations.get(lang).map(String::as_str),
+    }
+}
+
+/// Display name of an ISO 3166-1 alpha-2 or alpha-3 code in `lang`.
+pub fn localized_name_by_code(code: &str, lang: &str) -> Option<&'static str> {
+    localized_name(country_by_code(code)?, lang)
+}
+
+/// Resolves a display name in any bundled language ("Allemagne", "Deutschland", "德国") to its dataset key.
+pub fn country_from_localized(name: &str) -> Option<&'static str> {
+    let name = normalize(name);
+    names()
+        .iter()
+        .find(|(key, translations)| {
+            normalize(key) == name
+                || translations
+                    .values()
+                    .any(|translation| normalize(translation) == name)
+        })
+        .map(|(key, _)| key.as_str())
+}
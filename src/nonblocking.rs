@@ -0,0 +1,59 @@
+//! Async counterparts of `fetch` and `get_data`, enabled by the `async` feature, for callers
+//! already running a tokio runtime that don't want `reqwest::blocking` spinning up its own.
+
+use crate::cache::{CacheStore, FileCache};
+use crate::html::extract_tables;
+use crate::limits::{check_length, MAX_PAGE_BYTES};
+use crate::rate_limit::{rate_limited, USER_AGENT};
+use crate::{
+    embedded_default_expectancy, error_kind, parse_countries, receive_default_expectancy,
+    CountryInfo, ErrorKind, FETCH_URL,
+};
+use anyhow::Result;
+use reqwest::StatusCode;
+use std::collections::HashMap;
+
+/// A 429 fails with `Error::RateLimited` right away, waiting is up to the caller's runtime.
+pub async fn fetch_async() -> Result<HashMap<String, CountryInfo>> {
+    let resp = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()?
+        .get(FETCH_URL)
+        .send()
+        .await?;
+    if resp.status() == StatusCode::TOO_MANY_REQUESTS {
+        return Err(rate_limited(resp.headers()));
+    }
+    let mut resp = resp.error_for_status()?;
+    check_length(resp.content_length(), MAX_PAGE_BYTES, "The page")?;
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        body.extend_from_slice(&chunk);
+        check_length(Some(body.len() as u64), MAX_PAGE_BYTES, "The page")?;
+    }
+    parse_countries(&extract_tables(&body[..])?)
+}
+
+/// Same as `get_data`, with the fetch done on the caller's runtime. The cache is a plain file and is read synchronously.
+pub async fn get_data_async() -> Result<HashMap<String, CountryInfo>> {
+    let cache = FileCache::default();
+    if let Ok(Some(data)) = cache.load() {
+        return Ok(data);
+    }
+    match fetch_async().await {
+        Ok(data) => {
+            cache.store(&data)?;
+            Ok(data)
+        }
+        // Rate limiting is reported, the defaults would hide it
+        Err(err) if error_kind(&err) == ErrorKind::RateLimited => Err(err),
+        // Network error, use default expectancy data
+        Err(err) => {
+            tracing::warn!("fetch failed, using the bundled defaults: {}", err);
+            receive_default_expectancy().or_else(|err| {
+                tracing::warn!("{:#}, using the embedded defaults", err);
+                embedded_default_expectancy()
+            })
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Refresh notifications. Callers compute a `DataDiff` after a refresh and pass it to each
+//! `Notifier` when it isn't empty, closures taking a `&DataDiff` work as custom notifiers.
+
+use crate::{tagged, DataDiff};
+use anyhow::{anyhow, Result};
+#[cfg(feature = "notify")]
+use notify_rust::Notification;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+#[cfg(feature = "blocking")]
+use std::time::Duration;
+
+pub trait Notifier: Send + Sync {
+    fn notify(&self, diff: &DataDiff) -> Result<()>;
+}
+
+impl<F> Notifier for F
+where
+    F: Fn(&DataDiff) -> Result<()> + Send + Sync,
+{
+    fn notify(&self, diff: &DataDiff) -> Result<()> {
+        self(diff)
+    }
+}
+
+/// `{"event": "refresh", "summary": …, "diff": {"added", "removed", "changed"}}` tagged as a
+/// "refresh", as sent by `WebhookNotifier` and `CommandNotifier`.
+pub fn payload(diff: &DataDiff) -> Result<Value> {
+    tagged(
+        "refresh",
+        json!({
+            "event": "refresh",
+            "summary": diff.summary(),
+            "diff": diff,
+        }),
+    )
+}
+
+/// POSTs the JSON `payload` to a URL.
+#[cfg(feature = "blocking")]
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    url: String,
+}
+
+#[cfg(feature = "blocking")]
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>) -> Self {
+        WebhookNotifier { url: url.into() }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Notifier for WebhookNotifier {
+    fn notify(&self, diff: &DataDiff) -> Result<()> {
+        reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?
+            .post(&self.url)
+            .json(&payload(diff)?)
+            .send()?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+/// Desktop notification with the diff summary.
+#[cfg(feature = "notify")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DesktopNotifier;
+
+#[cfg(feature = "notify")]
+impl Notifier for DesktopNotifier {
+    fn notify(&self, diff: &DataDiff) -> Result<()> {
+        Notification::new()
+            .appname("lifespan")
+            .summary("Life expectancy data updated")
+            .body(&diff.summary())
+            .show()?;
+
+        Ok(())
+    }
+}
+
+/// Runs a program with the JSON `payload` on stdin and the summary in `LIFESPAN_SUMMARY`,
+/// failing when it exits unsuccessfully.
+#[derive(Debug, Clone)]
+pub struct CommandNotifier {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandNotifier {
+    pub fn new(program: impl Into<String>) -> Self {
+        CommandNotifier {
+            program: program.into(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+}
+
+impl Notifier for CommandNotifier {
+    fn notify(&self, diff: &DataDiff) -> Result<()> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .env("LIFESPAN_SUMMARY", diff.summary())
+            .stdin(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(serde_json::to_string(&payload(diff)?)?.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(anyhow!("{} exited with {}", self.program, status));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use crate::robots::Robots;
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Runs the jobs on at most `limit` scoped threads and returns the results in order.
+#[cfg(any(feature = "history", feature = "subnational"))]
+pub(crate) fn join_all<T, F>(jobs: Vec<F>, limit: usize) -> Vec<T>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    let count = jobs.len();
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(count));
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..limit.clamp(1, count.max(1)))
+            .map(|_| {
+                scope.spawn(|| loop {
+                    let next = queue.lock().unwrap().next();
+                    let Some((index, job)) = next else {
+                        break;
+                    };
+                    let result = job();
+                    results.lock().unwrap().push((index, result));
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Runs `a` and `b` at the same time, `b` on a scoped thread, and returns both results.
+#[cfg(feature = "gdp")]
+pub(crate) fn join<A, B>(a: impl FnOnce() -> A, b: impl FnOnce() -> B + Send) -> (A, B)
+where
+    B: Send,
+{
+    thread::scope(|scope| {
+        let b = scope.spawn(b);
+        let a = a();
+        let b = b
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        (a, b)
+    })
+}
+
+/// Spaces out requests to the same host by at least `delay`, across threads, optionally
+/// checking each host's robots.txt first.
+pub(crate) struct HostLimiter {
+    delay: Duration,
+    next: Mutex<HashMap<String, Instant>>,
+    robots: Option<Mutex<HashMap<String, Robots>>>,
+}
+
+impl HostLimiter {
+    pub(crate) fn new(delay: Duration) -> Self {
+        HostLimiter {
+            delay,
+            next: Mutex::new(HashMap::new()),
+            robots: None,
+        }
+    }
+
+    pub(crate) fn with_robots(mut self) -> Self {
+        self.robots = Some(Mutex::new(HashMap::new()));
+        self
+    }
+
+    /// Blocks until a request to `url` is allowed, failing when robots.txt disallows it.
+    pub(crate) fn wait(&self, url: &str) -> Result<()> {
+        let url = Url::parse(url)?;
+        let host = url.host_str().unwrap_or_default().to_string();
+        let mut delay = self.delay;
+        if let Some(robots) = &self.robots {
+            // Held across the robots.txt fetch so each host's file is only requested once
+            let mut robots = robots.lock().unwrap();
+            if !robots.contains_key(&host) {
+                robots.insert(host.clone(), Robots::fetch(&url)?);
+            }
+            let rules = &robots[&host];
+            if !rules.allows(url.path()) {
+                return Err(anyhow!("{} is disallowed by robots.txt", url));
+            }
+            delay = delay.max(rules.crawl_delay().unwrap_or_default());
+        }
+
+        let now = Instant::now();
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = next.get(&host).copied().unwrap_or(now).max(now);
+            // Reserve the slot before sleeping so concurrent callers queue up behind it
+            next.insert(host, slot + delay);
+            slot
+        };
+        thread::sleep(slot - now);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+//! Transforms compiled to WebAssembly, run by wasmtime.
+//!
+//! A plugin exports its `memory` along with
+//! - `alloc(len: i32) -> i32`, an address where the host writes a `len` bytes input
+//! - `transform(ptr: i32, len: i32) -> i64`, reading the input there and returning the address
+//!   of its output in the high 32 bits and its length in the low ones
+//!
+//! Input and output are the dataset as a UTF-8 JSON object, `{"Japan": {"all": 84.5, "male":
+//! 81.5, "female": 87.6}, ...}`. Every call starts from a fresh instance, so a plugin needn't free
+//! anything, and stops after `FUEL` units of work.
+
+use crate::transform::Transform;
+use crate::CountryInfo;
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use wasmtime::{Config, Engine, Instance, Module, Store};
+
+/// Work a call may do, about a few seconds' worth, before it is stopped.
+pub const FUEL: u64 = 10_000_000_000;
+
+pub struct WasmPlugin {
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmPlugin {
+    /// Compiles a `.wasm` module, or its `.wat` text form.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let engine = engine()?;
+        let module = Module::from_file(&engine, path)
+            .with_context(|| format!("Invalid plugin {}", path.display()))?;
+        Ok(WasmPlugin { engine, module })
+    }
+
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self> {
+        let engine = engine()?;
+        let module = Module::new(&engine, bytes)?;
+        Ok(WasmPlugin { engine, module })
+    }
+
+    fn call(&self, input: &[u8]) -> Result<Vec<u8>> {
+        let mut store = Store::new(&self.engine, ());
+        store.set_fuel(FUEL)?;
+        let instance = Instance::new(&mut store, &self.module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("The plugin exports no memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let transform = instance.get_typed_func::<(i32, i32), i64>(&mut store, "transform")?;
+
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+        let packed = transform
+            .call(&mut store, (ptr, len))
+            .context("The plugin failed")? as u64;
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        let mut output = vec![0; len];
+        memory
+            .read(&store, ptr, &mut output)
+            .context("The plugin returned an output out of its memory")?;
+
+        Ok(output)
+    }
+}
+
+impl Transform for WasmPlugin {
+    fn apply(&self, data: HashMap<String, CountryInfo>) -> Result<HashMap<String, CountryInfo>> {
+        let output = self.call(&serde_json::to_vec(&data)?)?;
+        serde_json::from_slice(&output).context("The plugin returned invalid data")
+    }
+}
+
+fn engine() -> Result<Engine> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config)
+}
@@ -0,0 +1,48 @@
+#[cfg(feature = "blocking")]
+use std::io::{self, Read};
+
+/// Step of a cold refresh reported to the `on_progress` callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Downloading,
+    Parsing,
+    Caching,
+}
+
+/// Called with the current phase and how far along it is, from 0.0 to 1.0.
+pub type Progress = dyn Fn(Phase, f32) + Send + Sync;
+
+/// Reports the share of `total` bytes read so far while downloading.
+#[cfg(feature = "blocking")]
+pub(crate) struct ProgressReader<'a, R> {
+    inner: R,
+    read: u64,
+    total: Option<u64>,
+    progress: &'a Progress,
+}
+
+#[cfg(feature = "blocking")]
+impl<'a, R: Read> ProgressReader<'a, R> {
+    pub(crate) fn new(inner: R, total: Option<u64>, progress: &'a Progress) -> Self {
+        ProgressReader {
+            inner,
+            read: 0,
+            total,
+            progress,
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read += read as u64;
+        // Without a Content-Length only the start and the end of the download are reported
+        if let Some(total) = self.total.filter(|total| *total > 0) {
+            let fraction = (self.read as f64 / total as f64).min(1.0);
+            (self.progress)(Phase::Downloading, fraction as f32);
+        }
+        Ok(read)
+    }
+}
@@ -0,0 +1,133 @@
+//! Where the cached data came from, recorded on every fetch so it can be attributed.
+
+use crate::date::iso_date;
+use crate::{get_tmp_dir, read_tmp_file, write_tmp_file};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+#[cfg(feature = "blocking")]
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::SystemTime;
+
+pub(crate) const FILE_NAME: &str = ".tmp_provenance.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub source_url: String,
+    /// Revision id of the article, when the page states it.
+    pub revision: Option<u64>,
+    /// `ETag` header of the response.
+    pub etag: Option<String>,
+    pub retrieved_at: SystemTime,
+}
+
+impl Provenance {
+    /// Retrieved now, revision and ETag unknown.
+    pub fn new(source_url: impl Into<String>) -> Self {
+        Provenance {
+            source_url: source_url.into(),
+            revision: None,
+            etag: None,
+            retrieved_at: SystemTime::now(),
+        }
+    }
+
+    /// Retrieval date, `YYYY-MM-DD`.
+    pub fn retrieved_on(&self) -> String {
+        iso_date(self.retrieved_at)
+    }
+
+    /// Link to the exact revision read, the source URL when it is unknown.
+    pub fn permalink(&self) -> String {
+        match self.revision {
+            Some(revision) => format!("{}?oldid={}", self.source_url, revision),
+            None => self.source_url.clone(),
+        }
+    }
+}
+
+/// Provenance of the data last fetched into the cache directory by a client with the default cache.
+pub fn load() -> Result<Option<Provenance>> {
+    load_from(&get_tmp_dir().join(FILE_NAME))
+}
+
+pub(crate) fn load_from(path: &Path) -> Result<Option<Provenance>> {
+    read_tmp_file(path)
+}
+
+// A source that can't tell removes what an earlier fetch recorded
+pub(crate) fn store(path: &Path, provenance: Option<&Provenance>) -> Result<()> {
+    match provenance {
+        Some(provenance) => write_tmp_file(path, provenance),
+        None if path.is_file() => Ok(fs::remove_file(path)?),
+        None => Ok(()),
+    }
+}
+
+// `"wgRevisionId":1234` sits in a script of the page head
+#[cfg(feature = "blocking")]
+const REVISION_MARKER: &[u8] = b"\"wgRevisionId\":";
+// Marker and digits, kept between reads so neither is missed when split by a read
+#[cfg(feature = "blocking")]
+const WINDOW: usize = 64;
+
+/// The revision id stated in `page`, `None` when digits run until its end unless `complete`.
+#[cfg(feature = "blocking")]
+pub(crate) fn revision_in(page: &[u8], complete: bool) -> Option<u64> {
+    let start = page
+        .windows(REVISION_MARKER.len())
+        .position(|window| window == REVISION_MARKER)?
+        + REVISION_MARKER.len();
+    let digits = page[start..]
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    if start + digits == page.len() && !complete {
+        return None;
+    }
+
+    std::str::from_utf8(&page[start..start + digits])
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Passes a page through, picking up its revision id on the way since `extract_tables` drops scripts.
+#[cfg(feature = "blocking")]
+pub(crate) struct RevisionReader<R> {
+    inner: R,
+    window: Vec<u8>,
+    revision: Option<u64>,
+}
+
+#[cfg(feature = "blocking")]
+impl<R: Read> RevisionReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        RevisionReader {
+            inner,
+            window: Vec::new(),
+            revision: None,
+        }
+    }
+
+    pub(crate) fn revision(&self) -> Option<u64> {
+        self.revision
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<R: Read> Read for RevisionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if self.revision.is_none() {
+            self.window.extend_from_slice(&buf[..read]);
+            self.revision = revision_in(&self.window, read == 0);
+            if self.window.len() > WINDOW {
+                self.window.drain(..self.window.len() - WINDOW);
+            }
+        }
+
+        Ok(read)
+    }
+}
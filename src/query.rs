@@ -0,0 +1,460 @@
+//! Filtering, sorting and truncating a dataset in one chain, built in code or parsed from
+//! `sex=female AND all>80 SORT BY gap DESC LIMIT 10`.
+
+use crate::countries::{who_region, WhoRegion};
+use crate::{CountryInfo, Dataset, Sex, COMMON};
+use anyhow::{anyhow, bail, Result};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::FromStr;
+use std::vec::IntoIter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Order {
+    #[default]
+    Name,
+    Ascending,
+    Descending,
+}
+
+/// A value of an entry conditions and sorting can look at.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    All,
+    Male,
+    Female,
+    /// See `CountryInfo::gap`.
+    Gap,
+    /// See `CountryInfo::female_to_male_ratio`.
+    Ratio,
+}
+
+impl Field {
+    /// `None` for a ratio without a male value.
+    pub fn of(self, info: &CountryInfo) -> Option<f64> {
+        match self {
+            Field::All => Some(info.all),
+            Field::Male => Some(info.male),
+            Field::Female => Some(info.female),
+            Field::Gap => Some(info.gap()),
+            Field::Ratio => info.female_to_male_ratio(),
+        }
+    }
+}
+
+impl From<Sex> for Field {
+    fn from(sex: Sex) -> Self {
+        match sex {
+            Sex::All => Field::All,
+            Sex::Male => Field::Male,
+            Sex::Female => Field::Female,
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "gap" => Ok(Field::Gap),
+            "ratio" => Ok(Field::Ratio),
+            _ => match s.parse::<Sex>() {
+                Ok(sex) => Ok(Field::from(sex)),
+                Err(_) => Err(anyhow!("Unknown field: {}", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::All => write!(f, "all"),
+            Field::Male => write!(f, "male"),
+            Field::Female => write!(f, "female"),
+            Field::Gap => write!(f, "gap"),
+            Field::Ratio => write!(f, "ratio"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    pub fn holds(self, value: f64, bound: f64) -> bool {
+        match self {
+            Comparison::Less => value < bound,
+            Comparison::LessOrEqual => value <= bound,
+            Comparison::Equal => value == bound,
+            Comparison::NotEqual => value != bound,
+            Comparison::GreaterOrEqual => value >= bound,
+            Comparison::Greater => value > bound,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "<" => Ok(Comparison::Less),
+            "<=" => Ok(Comparison::LessOrEqual),
+            "=" | "==" => Ok(Comparison::Equal),
+            "!=" | "<>" => Ok(Comparison::NotEqual),
+            ">=" => Ok(Comparison::GreaterOrEqual),
+            ">" => Ok(Comparison::Greater),
+            _ => Err(anyhow!("Unknown comparison: {}", s)),
+        }
+    }
+}
+
+/// `Query::new().region(WhoRegion::Europe).sex(Sex::Female).min(80.0).sort_desc().limit(10)`,
+/// every entry but "Common" sorted by name unless narrowed down.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Query {
+    region: Option<WhoRegion>,
+    sex: Option<Sex>,
+    min: Option<f64>,
+    max: Option<f64>,
+    filters: Vec<(Field, Comparison, f64)>,
+    sort_by: Option<Field>,
+    order: Order,
+    limit: Option<usize>,
+}
+
+/// An entry a query kept, `value` being the one of the queried sex.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueryRow {
+    pub country: String,
+    pub info: CountryInfo,
+    pub value: f64,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Query::default()
+    }
+
+    /// Only countries of a WHO region, entries without metadata never match.
+    pub fn region(mut self, region: WhoRegion) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    /// Sex `min`, `max` and sorting look at, `Sex::All` by default.
+    pub fn sex(mut self, sex: Sex) -> Self {
+        self.sex = Some(sex);
+        self
+    }
+
+    /// Inclusive lower bound.
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Inclusive upper bound.
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Keeps the entries whose `field` compares to `value` as `comparison` says, every filter
+    /// having to hold.
+    pub fn filter(mut self, field: Field, comparison: Comparison, value: f64) -> Self {
+        self.filters.push((field, comparison, value));
+        self
+    }
+
+    /// Sorts on `field` rather than the queried sex, lowest first unless `sort_desc`.
+    pub fn sort_by(mut self, field: Field) -> Self {
+        self.sort_by = Some(field);
+        self
+    }
+
+    /// Highest value first.
+    pub fn sort_desc(mut self) -> Self {
+        self.order = Order::Descending;
+        self
+    }
+
+    /// Lowest value first.
+    pub fn sort_asc(mut self) -> Self {
+        self.order = Order::Ascending;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Field rows are sorted on, the queried sex unless `sort_by` says otherwise.
+    pub fn key(&self) -> Field {
+        self.sort_by
+            .unwrap_or(Field::from(self.sex.unwrap_or(Sex::All)))
+    }
+
+    pub fn run(&self, dataset: &Dataset) -> Vec<QueryRow> {
+        let sex = self.sex.unwrap_or(Sex::All);
+        let key = self.key();
+        let order = match (self.order, self.sort_by) {
+            (Order::Name, Some(_)) => Order::Ascending,
+            (order, _) => order,
+        };
+        #[cfg(feature = "rayon")]
+        let entries = dataset.countries().par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let entries = dataset.countries().iter();
+        let mut rows: Vec<QueryRow> = entries
+            .filter(|(name, _)| name.as_str() != COMMON)
+            .filter(|(name, _)| self.region.is_none() || who_region(name) == self.region)
+            .filter(|(_, info)| {
+                self.filters.iter().all(|(field, comparison, value)| {
+                    field
+                        .of(info)
+                        .is_some_and(|of| comparison.holds(of, *value))
+                })
+            })
+            .map(|(name, info)| QueryRow {
+                country: name.to_string(),
+                info: info.clone(),
+                value: info.get(sex),
+            })
+            .filter(|row| self.min.is_none_or(|min| row.value >= min))
+            .filter(|row| self.max.is_none_or(|max| row.value <= max))
+            .collect();
+        let compare = |a: &QueryRow, b: &QueryRow| {
+            let by_value = match (order, key.of(&a.info), key.of(&b.info)) {
+                (Order::Name, _, _) => Ordering::Equal,
+                (Order::Ascending, Some(a), Some(b)) => a.total_cmp(&b),
+                (Order::Descending, Some(a), Some(b)) => b.total_cmp(&a),
+                // Rows without a value go last in either order
+                (_, a, b) => a.is_none().cmp(&b.is_none()),
+            };
+            by_value.then_with(|| a.country.cmp(&b.country))
+        };
+        #[cfg(feature = "rayon")]
+        rows.par_sort_by(compare);
+        #[cfg(not(feature = "rayon"))]
+        rows.sort_by(compare);
+        rows.truncate(self.limit.unwrap_or(usize::MAX));
+
+        rows
+    }
+}
+
+/// `[condition {AND condition}] [SORT BY field [ASC|DESC]] [LIMIT n]`, keywords in any case.
+/// A condition is `field op number` with a `Field` and an operator among `< <= = != >= >`,
+/// or `sex=<sex>` and `region=<WHO region>`, quoted when it holds spaces.
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut tokens = tokenize(s).into_iter().peekable();
+        let mut query = Query::new();
+        if tokens.peek().is_some_and(|token| !is_clause(token)) {
+            loop {
+                query = condition(query, &mut tokens)?;
+                match tokens.peek() {
+                    Some(token) if keyword(token, "and") => tokens.next(),
+                    _ => break,
+                };
+            }
+        }
+        if next_keyword(&mut tokens, "sort") {
+            if !next_keyword(&mut tokens, "by") {
+                bail!("Expected BY after SORT");
+            }
+            query = query.sort_by(word(&mut tokens, "a field to sort by")?.parse()?);
+            if next_keyword(&mut tokens, "desc") {
+                query = query.sort_desc();
+            } else if next_keyword(&mut tokens, "asc") {
+                query = query.sort_asc();
+            }
+        }
+        if next_keyword(&mut tokens, "limit") {
+            let limit = word(&mut tokens, "a number after LIMIT")?;
+            query = query.limit(
+                limit
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid limit: {}", limit))?,
+            );
+        }
+        if let Some(token) = tokens.next() {
+            bail!("Unexpected {}", token);
+        }
+
+        Ok(query)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Operator(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{:?}", word),
+            Token::Operator(operator) => write!(f, "{}", operator),
+        }
+    }
+}
+
+const OPERATOR_CHARS: &[char] = &['<', '>', '=', '!'];
+
+fn tokenize(s: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let quoted: String = chars.by_ref().take_while(|&next| next != c).collect();
+            tokens.push(Token::Word(quoted));
+        } else if OPERATOR_CHARS.contains(&c) {
+            let mut operator = String::new();
+            while let Some(&next) = chars.peek().filter(|next| OPERATOR_CHARS.contains(next)) {
+                operator.push(next);
+                chars.next();
+            }
+            tokens.push(Token::Operator(operator));
+        } else {
+            let mut word = String::new();
+            while let Some(&next) = chars
+                .peek()
+                .filter(|next| !next.is_whitespace() && !OPERATOR_CHARS.contains(next))
+            {
+                word.push(next);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+
+    tokens
+}
+
+type Tokens = Peekable<IntoIter<Token>>;
+
+fn keyword(token: &Token, keyword: &str) -> bool {
+    matches!(token, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+}
+
+fn is_clause(token: &Token) -> bool {
+    keyword(token, "sort") || keyword(token, "limit")
+}
+
+fn next_keyword(tokens: &mut Tokens, expected: &str) -> bool {
+    tokens.next_if(|token| keyword(token, expected)).is_some()
+}
+
+fn word(tokens: &mut Tokens, expected: &str) -> Result<String> {
+    match tokens.next() {
+        Some(Token::Word(word)) => Ok(word),
+        Some(token) => bail!("Expected {}, found {}", expected, token),
+        None => bail!("Expected {}", expected),
+    }
+}
+
+fn condition(query: Query, tokens: &mut Tokens) -> Result<Query> {
+    let key = word(tokens, "a condition")?;
+    let comparison: Comparison = match tokens.next() {
+        Some(Token::Operator(operator)) => operator.parse()?,
+        _ => bail!("Expected a comparison after {}", key),
+    };
+    let value = word(tokens, &format!("a value for {}", key))?;
+    match key.to_lowercase().as_str() {
+        "sex" | "region" if comparison != Comparison::Equal => {
+            bail!("{} only supports =", key)
+        }
+        "sex" => Ok(query.sex(value.parse()?)),
+        "region" => Ok(query.region(value.parse()?)),
+        _ => {
+            let bound = value
+                .parse()
+                .map_err(|_| anyhow!("Invalid number for {}: {}", key, value))?;
+            Ok(query.filter(key.parse()?, comparison, bound))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_full_grammar() {
+        let query: Query =
+            "sex=female AND all>80 and region='south east asia' SORT BY gap DESC LIMIT 10"
+                .parse()
+                .unwrap();
+        let expected = Query::new()
+            .sex(Sex::Female)
+            .filter(Field::All, Comparison::Greater, 80.0)
+            .region(WhoRegion::SouthEastAsia)
+            .sort_by(Field::Gap)
+            .sort_desc()
+            .limit(10);
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn parses_clauses_alone() {
+        assert_eq!("".parse::<Query>().unwrap(), Query::new());
+        assert_eq!("limit 3".parse::<Query>().unwrap(), Query::new().limit(3));
+        assert_eq!(
+            "sort by ratio".parse::<Query>().unwrap(),
+            Query::new().sort_by(Field::Ratio)
+        );
+        assert_eq!(
+            "male<=70".parse::<Query>().unwrap(),
+            Query::new().filter(Field::Male, Comparison::LessOrEqual, 70.0)
+        );
+        assert_eq!(
+            "gap <> 5".parse::<Query>().unwrap(),
+            Query::new().filter(Field::Gap, Comparison::NotEqual, 5.0)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_queries() {
+        for query in [
+            "all",
+            "all >",
+            "all > x",
+            "all => 80",
+            "height > 80",
+            "sex > female",
+            "sex = other",
+            "region = mars",
+            "all > 80 or male > 70",
+            "sort gap",
+            "sort by",
+            "sort by height",
+            "limit",
+            "limit -1",
+            "limit 10 sort by gap",
+        ] {
+            assert!(query.parse::<Query>().is_err(), "{:?} parsed", query);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! 429 handling for requests to Wikipedia: short `Retry-After` waits are honored and retried,
+//! anything longer surfaces as `Error::RateLimited`.
+
+use crate::date::parse_http_date;
+use crate::Error;
+#[cfg(feature = "blocking")]
+use anyhow::Result;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+#[cfg(feature = "blocking")]
+use reqwest::StatusCode;
+use std::time::{Duration, SystemTime};
+
+/// Sent with every request to Wikipedia, robots.txt groups are matched on its product token.
+pub(crate) const USER_AGENT: &str = concat!(
+    "lifespan_crawler/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/liwuhou/lifespan-crawler)"
+);
+
+#[cfg(feature = "blocking")]
+const MAX_RETRIES: u32 = 2;
+// Longer waits are left to the caller rather than blocking it
+#[cfg(feature = "blocking")]
+const MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// `Retry-After` given in seconds or as an HTTP date.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = parse_http_date(value)?;
+    Some(at.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+pub(crate) fn rate_limited(headers: &HeaderMap) -> anyhow::Error {
+    Error::RateLimited {
+        retry_after: retry_after(headers),
+    }
+    .into()
+}
+
+/// GET that fails on an error status, waiting out and retrying short rate limits.
+/// Without `Retry-After` it backs off 1s then 2s.
+#[cfg(feature = "blocking")]
+pub(crate) fn get(url: &str) -> Result<reqwest::blocking::Response> {
+    get_within(url, None)
+}
+
+/// Same as `get`, each attempt given `timeout` rather than reqwest's 30 seconds.
+#[cfg(feature = "blocking")]
+pub(crate) fn get_within(
+    url: &str,
+    timeout: Option<Duration>,
+) -> Result<reqwest::blocking::Response> {
+    let mut http = reqwest::blocking::Client::builder().user_agent(USER_AGENT);
+    if let Some(timeout) = timeout {
+        http = http.timeout(timeout);
+    }
+    let http = http.build()?;
+    let mut attempt = 0;
+    loop {
+        let resp = http.get(url).send()?;
+        if resp.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(resp.error_for_status()?);
+        }
+        let wait = retry_after(resp.headers()).unwrap_or(Duration::from_secs(1 << attempt));
+        if attempt >= MAX_RETRIES || wait > MAX_WAIT {
+            return Err(rate_limited(resp.headers()));
+        }
+        tracing::warn!(url, wait = ?wait, "rate limited, retrying");
+        std::thread::sleep(wait);
+        attempt += 1;
+    }
+}
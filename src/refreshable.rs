@@ -0,0 +1,59 @@
+use crate::schedule::Schedule;
+use crate::{Client, Dataset};
+use anyhow::Result;
+use arc_swap::{ArcSwap, Guard};
+use std::sync::Arc;
+
+/// Value replaced as a whole by refreshes, reads never take a lock nor wait for a swap.
+pub struct Refreshable<T> {
+    current: ArcSwap<T>,
+}
+
+impl<T> Refreshable<T> {
+    pub fn new(value: T) -> Self {
+        Refreshable {
+            current: ArcSwap::from_pointee(value),
+        }
+    }
+
+    /// Cheapest read, for use within a request. Holding many guards at once degrades to `load_full`.
+    pub fn load(&self) -> Guard<Arc<T>> {
+        self.current.load()
+    }
+
+    /// A snapshot to keep beyond the current request.
+    pub fn load_full(&self) -> Arc<T> {
+        self.current.load_full()
+    }
+
+    pub fn store(&self, value: T) {
+        self.current.store(Arc::new(value));
+    }
+
+    /// Swaps in what `refresh` returns, keeping the current value when it fails.
+    pub fn refresh_with(&self, refresh: impl FnOnce() -> Result<T>) -> Result<()> {
+        self.store(refresh()?);
+        Ok(())
+    }
+}
+
+impl Refreshable<Dataset> {
+    pub fn load_from(client: &Client) -> Result<Self> {
+        Ok(Refreshable::new(client.dataset()?))
+    }
+
+    pub fn refresh(&self, client: &Client) -> Result<()> {
+        self.refresh_with(|| Ok(Dataset::new(client.refresh()?)))
+    }
+
+    /// Refreshes when `schedule` says so, returning whether it did.
+    pub fn refresh_if_due(&self, client: &Client, schedule: &Schedule) -> Result<bool> {
+        match client.refresh_if_due(schedule)? {
+            Some(data) => {
+                self.store(Dataset::new(data));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Location key, either a whole country or one of its subdivisions (state, nation, province…).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Region {
+    Country(String),
+    Subdivision { country: String, name: String },
+}
+
+impl Region {
+    pub fn country(&self) -> &str {
+        match self {
+            Region::Country(country) => country,
+            Region::Subdivision { country, .. } => country,
+        }
+    }
+
+    pub fn parent(&self) -> Option<Region> {
+        match self {
+            Region::Country(_) => None,
+            Region::Subdivision { country, .. } => Some(Region::Country(country.clone())),
+        }
+    }
+
+    pub fn is_within(&self, other: &Region) -> bool {
+        self == other || self.parent().as_ref() == Some(other)
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Region::Country(country) => write!(f, "{}", country),
+            Region::Subdivision { country, name } => write!(f, "{}, {}", name, country),
+        }
+    }
+}
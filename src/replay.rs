@@ -0,0 +1,53 @@
+//! Record/replay of the source page, enabled by the `replay` feature, so parser
+//! regressions can be checked against real historical snapshots.
+
+use crate::source::DataSource;
+use crate::{fetch_html, parse_countries, CountryInfo};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Fetches the live page like `WikipediaSource`, dumping the raw HTML to `path` before parsing it.
+pub struct RecordingSource {
+    path: PathBuf,
+}
+
+impl RecordingSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        RecordingSource { path: path.into() }
+    }
+}
+
+impl DataSource for RecordingSource {
+    fn fetch(&self) -> Result<HashMap<String, CountryInfo>> {
+        let html = fetch_html()?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, &html)
+            .with_context(|| format!("Failed to record {}", self.path.display()))?;
+
+        parse_countries(&html)
+    }
+}
+
+/// Parses a previously recorded page instead of hitting the network.
+pub struct ReplaySource {
+    path: PathBuf,
+}
+
+impl ReplaySource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        ReplaySource { path: path.into() }
+    }
+}
+
+impl DataSource for ReplaySource {
+    fn fetch(&self) -> Result<HashMap<String, CountryInfo>> {
+        let html = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to replay {}", self.path.display()))?;
+
+        parse_countries(&html)
+    }
+}
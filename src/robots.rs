@@ -0,0 +1,126 @@
+use crate::rate_limit;
+use anyhow::Result;
+use reqwest::Url;
+use std::time::Duration;
+
+// Matched against `User-agent` lines, `USER_AGENT` up to its version
+const PRODUCT_TOKEN: &str = "lifespan_crawler";
+
+/// The rules of a robots.txt that apply to this crate.
+#[derive(Debug, Default)]
+pub(crate) struct Robots {
+    // (allow, pattern)
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<Duration>,
+}
+
+fn is_client_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .and_then(reqwest::Error::status)
+        .is_some_and(|status| status.is_client_error())
+}
+
+impl Robots {
+    pub(crate) fn fetch(url: &Url) -> Result<Self> {
+        let robots_url = url.join("/robots.txt")?;
+        let resp = match rate_limit::get(robots_url.as_str()) {
+            Ok(resp) => resp,
+            // RFC 9309: a missing file allows everything, an unreachable one disallows everything.
+            // A rate limit is not a missing file, it surfaces as `Error::RateLimited`
+            Err(err) if is_client_error(&err) => return Ok(Robots::default()),
+            Err(err) => return Err(err.context(format!("Cannot read {}", robots_url))),
+        };
+
+        Ok(Robots::parse(&resp.text()?))
+    }
+
+    pub(crate) fn parse(content: &str) -> Self {
+        let mut ours = None;
+        let mut wildcard = None;
+        // Consecutive User-agent lines share the group that follows them
+        let mut agents: Vec<String> = Vec::new();
+        let mut group = Robots::default();
+        let mut in_rules = false;
+
+        let mut finish = |agents: &[String], group: Robots| {
+            if agents.iter().any(|agent| agent == PRODUCT_TOKEN) {
+                ours.get_or_insert(group);
+            } else if agents.iter().any(|agent| agent == "*") {
+                wildcard.get_or_insert(group);
+            }
+        };
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        finish(&agents, std::mem::take(&mut group));
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    agents.push(value.to_ascii_lowercase());
+                }
+                key @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    // An empty `Disallow:` allows everything
+                    if !value.is_empty() {
+                        group.rules.push((key == "allow", value.to_string()));
+                    }
+                }
+                "crawl-delay" => {
+                    in_rules = true;
+                    group.crawl_delay = value.parse::<f64>().ok().map(Duration::from_secs_f64);
+                }
+                _ => {}
+            }
+        }
+        finish(&agents, group);
+
+        ours.or(wildcard).unwrap_or_default()
+    }
+
+    /// Longest matching rule wins, `Allow` on a tie.
+    pub(crate) fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+
+    pub(crate) fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+}
+
+// `*` matches any run of characters, a trailing `$` anchors the end
+fn matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(first) = parts.next() else {
+        return true;
+    };
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (index, part) in parts.iter().enumerate() {
+        if anchored && index == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(found) => rest = &rest[found + part.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
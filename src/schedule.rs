@@ -0,0 +1,264 @@
+use crate::date::{civil_date, civil_from_days, to_system_time};
+use anyhow::{anyhow, bail, Result};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Yearly dates (month, day, UTC) after which new figures are usually out, so a refresh is
+/// only attempted once per window rather than on every run. The defaults follow the WHO
+/// World Health Statistics (May) and UN World Population Prospects (July) releases that the
+/// Wikipedia table is updated from, with a few weeks' margin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    windows: Vec<(u32, u32)>,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::new(vec![(6, 15), (8, 15)])
+    }
+}
+
+impl Schedule {
+    pub fn new(windows: Vec<(u32, u32)>) -> Self {
+        Schedule { windows }
+    }
+
+    pub fn windows(&self) -> &[(u32, u32)] {
+        &self.windows
+    }
+
+    /// Start of the first window after `last_refresh`.
+    pub fn next_after(&self, last_refresh: SystemTime) -> Option<SystemTime> {
+        let (year, _, _) = civil_date(last_refresh);
+        (year..=year + 1)
+            .flat_map(|year| {
+                self.windows
+                    .iter()
+                    .map(move |(month, day)| to_system_time(year, *month, *day))
+            })
+            .filter(|start| *start > last_refresh)
+            .min()
+    }
+
+    /// Whether a window has opened since `last_refresh`, always true without a previous refresh.
+    pub fn is_due(&self, last_refresh: Option<SystemTime>, now: SystemTime) -> bool {
+        match last_refresh {
+            Some(last_refresh) => self
+                .next_after(last_refresh)
+                .is_some_and(|start| start <= now),
+            None => true,
+        }
+    }
+}
+
+/// A five-field cron expression, `minute hour day-of-month month day-of-week` in UTC, each a `*`,
+/// a number, a `a-b` range or a list of them separated by commas, optionally stepped with `/n`.
+/// As in cron, a day matches either of the day fields when both are restricted. Sunday is 0 or 7.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+// Long enough for a February 29th, the rarest date five fields can ask for
+const SEARCH_DAYS: i64 = 8 * 366;
+
+impl Cron {
+    /// First matching minute strictly after `time`, `None` when no date can match
+    /// (`0 0 30 2 *` for instance).
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        // The next whole minute, `time` itself never matching
+        let start = secs / 60 + 1;
+        let (first_day, mut minute_of_day) = ((start / 1440) as i64, start % 1440);
+        for day in first_day..first_day + SEARCH_DAYS {
+            if self.matches_day(day) {
+                if let Some(minute) = (minute_of_day..1440)
+                    .find(|minute| has(self.hours, minute / 60) && has(self.minutes, minute % 60))
+                {
+                    return Some(
+                        UNIX_EPOCH + Duration::from_secs((day as u64 * 1440 + minute) * 60),
+                    );
+                }
+            }
+            minute_of_day = 0;
+        }
+
+        None
+    }
+
+    fn matches_day(&self, day: i64) -> bool {
+        let (_, month, day_of_month) = civil_from_days(day);
+        // 1970-01-01 was a Thursday
+        let weekday = (day + 4).rem_euclid(7) as u64;
+        let by_date = has(self.days, u64::from(day_of_month));
+        let by_weekday = has(self.weekdays, weekday);
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => by_weekday,
+            (false, true) => by_date,
+            (false, false) => by_date || by_weekday,
+        };
+
+        has(self.months, u64::from(month)) && day_matches
+    }
+}
+
+fn has(set: u64, value: u64) -> bool {
+    set & (1 << value) != 0
+}
+
+// Bit `n` set for every value `n` the field lists
+fn field(text: &str, name: &str, min: u64, max: u64) -> Result<u64> {
+    let mut set = 0;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u64 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| anyhow!("Invalid step in the {} field: {}", name, part))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let number = |text: &str| {
+            text.parse::<u64>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| anyhow!("The {} field takes {} to {}, not {}", name, min, max, text))
+        };
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((first, last)) => (number(first)?, number(last)?),
+            // `5/15` runs from 5 to the end, as in cron
+            None if step > 1 => (number(range)?, max),
+            None => (number(range)?, number(range)?),
+        };
+        if first > last {
+            bail!("Empty range in the {} field: {}", name, part);
+        }
+        for value in (first..=last).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+
+    Ok(set)
+}
+
+impl FromStr for Cron {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            bail!(
+                "A cron expression has 5 fields, minute hour day month weekday: {}",
+                s
+            );
+        };
+        let mut weekday_set = field(weekdays, "weekday", 0, 7)?;
+        // 7 is another Sunday
+        if has(weekday_set, 7) {
+            weekday_set = (weekday_set | 1) & !(1 << 7);
+        }
+
+        Ok(Cron {
+            expression: fields.join(" "),
+            minutes: field(minutes, "minute", 0, 59)?,
+            hours: field(hours, "hour", 0, 23)?,
+            days: field(days, "day", 1, 31)?,
+            months: field(months, "month", 1, 12)?,
+            weekdays: weekday_set,
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fields() {
+        let cron: Cron = "0,30 4 1-7 */3 1-5".parse().unwrap();
+        assert_eq!(cron.minutes, 1 | 1 << 30);
+        assert_eq!(cron.hours, 1 << 4);
+        assert_eq!(cron.days, 0b1111_1110);
+        assert_eq!(cron.months, 1 << 1 | 1 << 4 | 1 << 7 | 1 << 10);
+        assert_eq!(cron.weekdays, 0b11_1110);
+        assert!(!cron.any_day && !cron.any_weekday);
+        assert_eq!(cron.to_string(), "0,30 4 1-7 */3 1-5");
+    }
+
+    #[test]
+    fn sunday_is_0_or_7() {
+        let seven: Cron = "0 0 * * 7".parse().unwrap();
+        let zero: Cron = "0 0 * * 0".parse().unwrap();
+        assert_eq!(seven.weekdays, zero.weekdays);
+    }
+
+    #[test]
+    fn steps_run_from_a_start_to_the_end() {
+        let cron: Cron = "5/20 * * * *".parse().unwrap();
+        assert_eq!(cron.minutes, 1 << 5 | 1 << 25 | 1 << 45);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expression in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+            "1,,2 * * * *",
+        ] {
+            assert!(
+                expression.parse::<Cron>().is_err(),
+                "{:?} parsed",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn next_after_finds_the_next_match() {
+        let cron: Cron = "0 4 1 * *".parse().unwrap();
+        let next = cron.next_after(to_system_time(2024, 1, 15)).unwrap();
+        assert_eq!(
+            next,
+            to_system_time(2024, 2, 1) + Duration::from_secs(4 * 3600)
+        );
+        // Strictly after
+        assert_eq!(
+            cron.next_after(next),
+            Some(to_system_time(2024, 3, 1) + Duration::from_secs(4 * 3600))
+        );
+        let never: Cron = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(never.next_after(to_system_time(2024, 1, 1)), None);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::provenance::Provenance;
+use crate::CountryInfo;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+
+// Wider than any country has ever been, the oldest person on record reached 122
+const PLAUSIBLE_EXPECTANCY: RangeInclusive<f64> = 1.0..=125.0;
+
+/// Shape cached data must have, stricter than deserializing which ignores unknown fields.
+pub(crate) trait Schema: DeserializeOwned {
+    /// Why `value` could not have been written by this crate, checked before deserializing it.
+    fn check(value: &Value) -> Result<(), String>;
+}
+
+impl Schema for CountryInfo {
+    fn check(value: &Value) -> Result<(), String> {
+        let object = value.as_object().ok_or("expected an entry object")?;
+        if let Some(field) = object
+            .keys()
+            .find(|field| !["all", "male", "female"].contains(&field.as_str()))
+        {
+            return Err(format!("unknown field {:?}", field));
+        }
+        for field in ["all", "male", "female"] {
+            let number = object
+                .get(field)
+                .ok_or_else(|| format!("missing field {:?}", field))?
+                .as_f64()
+                .ok_or_else(|| format!("{:?} is not a number", field))?;
+            if !PLAUSIBLE_EXPECTANCY.contains(&number) {
+                return Err(format!("{} of {:?} is out of range", number, field));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Schema for Provenance {
+    fn check(value: &Value) -> Result<(), String> {
+        let object = value.as_object().ok_or("expected a provenance object")?;
+        if let Some(field) = object.keys().find(|field| {
+            !["source_url", "revision", "etag", "retrieved_at"].contains(&field.as_str())
+        }) {
+            return Err(format!("unknown field {:?}", field));
+        }
+        if !object.get("source_url").is_some_and(Value::is_string) {
+            return Err("missing source URL".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl<V: Schema> Schema for HashMap<String, V> {
+    fn check(value: &Value) -> Result<(), String> {
+        let object = value.as_object().ok_or("expected an object of entries")?;
+        for (key, entry) in object {
+            if key.trim().is_empty() {
+                return Err("blank key".to_string());
+            }
+            V::check(entry).map_err(|reason| format!("{}: {}", key, reason))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<V: Schema> Schema for BTreeMap<u32, V> {
+    fn check(value: &Value) -> Result<(), String> {
+        let object = value.as_object().ok_or("expected an object of years")?;
+        for (key, entry) in object {
+            key.parse::<u32>()
+                .map_err(|_| format!("{:?} is not a year", key))?;
+            V::check(entry).map_err(|reason| format!("{}: {}", key, reason))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes cached JSON once it passes `T::check`.
+pub(crate) fn parse_strict<T: Schema>(json: &str) -> Result<T, String> {
+    let value: Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    T::check(&value)?;
+
+    serde_json::from_value(value).map_err(|err| err.to_string())
+}
@@ -0,0 +1,101 @@
+//! Rhai scripts run over each fetched entry, for renames and adjustments without a plugin.
+//!
+//! The script sees `name`, `all`, `male` and `female` and may change them, an entry is dropped
+//! when the script evaluates to `false`, `return false` for instance. "Common" is recomputed
+//! from what is left rather than run through the script.
+//!
+//! ```rhai
+//! if name == "Turkey" { name = "Türkiye"; }
+//! if all < 50.0 { return false; }
+//! ```
+
+use crate::transform::Transform;
+use crate::{calculate_common, CountryInfo, COMMON};
+use anyhow::{anyhow, Context, Result};
+use rhai::{Dynamic, Engine, Scope, AST};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Operations one run of the script may take, so a runaway loop fails the fetch instead of hanging it.
+pub const MAX_OPERATIONS: u64 = 1_000_000;
+
+pub struct RhaiScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl RhaiScript {
+    pub fn new(source: &str) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile(source)
+            .map_err(|err| anyhow!("Invalid script: {}", err))?;
+        Ok(RhaiScript { engine, ast })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read script {}", path.display()))?;
+        RhaiScript::new(&source).with_context(|| format!("In {}", path.display()))
+    }
+
+    // `None` when the script drops the entry
+    fn run(&self, name: String, info: CountryInfo) -> Result<Option<(String, CountryInfo)>> {
+        let mut scope = Scope::new();
+        scope.push("name", name.clone());
+        scope.push("all", info.all);
+        scope.push("male", info.male);
+        scope.push("female", info.female);
+        let result: Dynamic = self
+            .engine
+            .eval_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|err| anyhow!("The script failed on {}: {}", name, err))?;
+        if result.as_bool() == Ok(false) {
+            return Ok(None);
+        }
+
+        let number = |variable: &str| {
+            scope
+                .get(variable)
+                .and_then(|value| {
+                    value
+                        .as_float()
+                        .ok()
+                        .or_else(|| value.as_int().ok().map(|int| int as f64))
+                })
+                .ok_or_else(|| anyhow!("The script left {} of {} not a number", variable, name))
+        };
+        let info = CountryInfo {
+            all: number("all")?,
+            male: number("male")?,
+            female: number("female")?,
+        };
+        let name = scope
+            .get_value::<String>("name")
+            .ok_or_else(|| anyhow!("The script left the name of {} not a string", name))?;
+
+        Ok(Some((name, info)))
+    }
+}
+
+impl Transform for RhaiScript {
+    fn apply(
+        &self,
+        mut data: HashMap<String, CountryInfo>,
+    ) -> Result<HashMap<String, CountryInfo>> {
+        let common = data.remove(COMMON).is_some();
+        let mut result = HashMap::new();
+        for (name, info) in data {
+            if let Some((name, info)) = self.run(name, info)? {
+                result.insert(name, info);
+            }
+        }
+        if common {
+            result.insert(String::from(COMMON), calculate_common(&result));
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::schedule::Schedule;
+use crate::{Client, Dataset};
+use anyhow::Result;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, RwLock};
+use std::time::Duration;
+
+static GLOBAL: OnceLock<SharedDataset> = OnceLock::new();
+
+/// Dataset handle shared between threads, see `Dataset::shared`. Clones share the same data.
+#[derive(Clone)]
+pub struct SharedDataset {
+    current: Arc<RwLock<Arc<Dataset>>>,
+    client: Arc<Client>,
+    // Concurrent refreshes would fetch the same page twice
+    refreshing: Arc<Mutex<()>>,
+}
+
+impl SharedDataset {
+    /// Loads through `client` once, later refreshes going through it as well.
+    pub fn new(client: Client) -> Result<Self> {
+        let dataset = client.dataset()?;
+        Ok(SharedDataset {
+            current: Arc::new(RwLock::new(Arc::new(dataset))),
+            client: Arc::new(client),
+            refreshing: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// The current data, which stays valid while a refresh replaces it.
+    pub fn get(&self) -> Arc<Dataset> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Fetches without holding the lock, readers only wait for the swap. On failure the current data is kept.
+    pub fn refresh(&self) -> Result<()> {
+        let _refreshing = self
+            .refreshing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let dataset = Dataset::new(self.client.refresh()?);
+        self.replace(dataset);
+        Ok(())
+    }
+
+    /// Refreshes when `schedule` says so, returning whether it did.
+    pub fn refresh_if_due(&self, schedule: &Schedule) -> Result<bool> {
+        let _refreshing = self
+            .refreshing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match self.client.refresh_if_due(schedule)? {
+            Some(data) => {
+                self.replace(Dataset::new(data));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Refreshes when the cache is older than `max_age`, see `Client::refresh_if_older_than`.
+    pub fn refresh_if_older_than(&self, max_age: Duration) -> Result<bool> {
+        let _refreshing = self
+            .refreshing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match self.client.refresh_if_stale(max_age)? {
+            Some(data) => {
+                self.replace(Dataset::new(data));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn replace(&self, dataset: Dataset) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(dataset);
+    }
+}
+
+impl Dataset {
+    /// Handle for multi-threaded readers with a background refresh, loaded through a default `Client`.
+    pub fn shared() -> Result<SharedDataset> {
+        SharedDataset::new(Client::new())
+    }
+}
+
+/// The process' dataset, loaded through a default `Client` on the first call and kept until
+/// `global_refresh`. A failed load is retried by the next call. Build a `Client` for anything
+/// more specific.
+pub fn global() -> Result<Arc<Dataset>> {
+    Ok(global_shared()?.get())
+}
+
+/// Fetches the process' dataset again, keeping the current one when it fails.
+pub fn global_refresh() -> Result<Arc<Dataset>> {
+    let shared = global_shared()?;
+    shared.refresh()?;
+    Ok(shared.get())
+}
+
+fn global_shared() -> Result<&'static SharedDataset> {
+    if let Some(shared) = GLOBAL.get() {
+        return Ok(shared);
+    }
+    // Callers racing for the first load wait for it rather than fetching too
+    static LOADING: Mutex<()> = Mutex::new(());
+    let _loading = LOADING.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(shared) = GLOBAL.get() {
+        return Ok(shared);
+    }
+    let shared = Dataset::shared()?;
+    Ok(GLOBAL.get_or_init(|| shared))
+}
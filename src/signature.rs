@@ -0,0 +1,58 @@
+//! Ed25519 signatures of the default datasets, so a `default_expectancy.json` replaced out of
+//! band (a release asset for instance) is only trusted when the project signed it.
+
+use anyhow::{anyhow, Context, Result};
+use ed25519_dalek::{Signature, VerifyingKey};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Ed25519 public keys, in hex, of the maintainers' signing keys for the default datasets.
+/// The previous key stays listed during a rotation, see "Signing the defaults" in the README.
+/// Empty until a maintainer adds theirs, so only the embedded defaults are trusted.
+pub const DEFAULTS_PUBLIC_KEYS: &[&str] = &[];
+
+/// Where the signature of `path` is looked for, `default_expectancy.json.sig` next to
+/// `default_expectancy.json` for instance. It holds the 64 signature bytes in hex.
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Fails unless `signature`, hex as in a `.sig` file, is a signature of `bytes` by one of
+/// `DEFAULTS_PUBLIC_KEYS`.
+pub fn verify(bytes: &[u8], signature: &str) -> Result<()> {
+    if DEFAULTS_PUBLIC_KEYS.is_empty() {
+        return Err(anyhow!("No key to check signatures with in this build"));
+    }
+    let signature = Signature::from_bytes(&hex_array(signature.trim())?);
+    for key in DEFAULTS_PUBLIC_KEYS {
+        let key = VerifyingKey::from_bytes(&hex_array(key)?)?;
+        if key.verify_strict(bytes, &signature).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(anyhow!("The signature does not match"))
+}
+
+/// The content of `path`, once its `.sig` is checked.
+pub fn read_verified(path: &Path) -> Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    let signature_path = signature_path(path);
+    let signature = fs::read_to_string(&signature_path)
+        .with_context(|| format!("No signature at {}", signature_path.display()))?;
+    verify(&bytes, &signature).with_context(|| format!("Untrusted {}", path.display()))?;
+    Ok(bytes)
+}
+
+fn hex_array<const N: usize>(hex: &str) -> Result<[u8; N]> {
+    let invalid = || anyhow!("Expected {} hex bytes", N);
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut bytes = [0; N];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
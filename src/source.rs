@@ -0,0 +1,162 @@
+#[cfg(feature = "blocking")]
+use crate::parallel::HostLimiter;
+use crate::progress::{Phase, Progress};
+use crate::provenance::Provenance;
+#[cfg(feature = "blocking")]
+use crate::tables::{parse_publisher, parse_selected, Publisher, TableSelector};
+#[cfg(feature = "blocking")]
+use crate::transport::Transport;
+use crate::CountryInfo;
+#[cfg(feature = "blocking")]
+use crate::{
+    article_url, download_tables_from, download_tables_through, parse_countries, FETCH_URL,
+};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+#[cfg(feature = "blocking")]
+use std::sync::{Arc, OnceLock};
+#[cfg(feature = "blocking")]
+use std::time::Duration;
+
+/// Where fresh data comes from when the cache is empty.
+pub trait DataSource: Send + Sync {
+    fn fetch(&self) -> Result<HashMap<String, CountryInfo>>;
+
+    /// Same as `fetch`, reporting the download and parse as they go. Sources that can't tell
+    /// how far along they are can keep the default, which only reports when they're done.
+    fn fetch_with_progress(&self, progress: &Progress) -> Result<HashMap<String, CountryInfo>> {
+        let result = self.fetch()?;
+        progress(Phase::Downloading, 1.0);
+        progress(Phase::Parsing, 1.0);
+        Ok(result)
+    }
+
+    /// Same as `fetch_with_progress`, along with where the data came from when the source can tell.
+    fn fetch_with_provenance(
+        &self,
+        progress: &Progress,
+    ) -> Result<(HashMap<String, CountryInfo>, Option<Provenance>)> {
+        Ok((self.fetch_with_progress(progress)?, None))
+    }
+}
+
+/// The Wikipedia "List of countries by life expectancy" article, streamed through `reqwest`
+/// unless built `with_transport`.
+#[cfg(feature = "blocking")]
+#[derive(Clone, Default)]
+pub struct WikipediaSource {
+    pub(crate) transport: Option<Arc<dyn Transport>>,
+    publisher: Option<Publisher>,
+    table: Option<TableSelector>,
+    revision: Option<u64>,
+    timeout: Option<Duration>,
+    robots: bool,
+}
+
+#[cfg(feature = "blocking")]
+impl WikipediaSource {
+    pub fn new() -> Self {
+        WikipediaSource::default()
+    }
+
+    pub fn with_transport(transport: impl Transport + 'static) -> Self {
+        WikipediaSource {
+            transport: Some(Arc::new(transport)),
+            ..WikipediaSource::default()
+        }
+    }
+
+    /// Takes the figures of `publisher`'s table, failing when the article has none, instead of
+    /// the table the article has always carried its main list in.
+    pub fn publisher(mut self, publisher: Publisher) -> Self {
+        self.publisher = Some(publisher);
+        self
+    }
+
+    /// Reads the table `selector` picks, taking precedence over `publisher`.
+    pub fn table(mut self, selector: TableSelector) -> Self {
+        self.table = Some(selector);
+        self
+    }
+
+    /// Reads the article as of revision `id`, see `Provenance::revision`, rather than its latest one.
+    pub fn revision(mut self, id: u64) -> Self {
+        self.revision = Some(id);
+        self
+    }
+
+    /// Gives up on a download attempt after `timeout`, 30 seconds unless set. A custom
+    /// `Transport` applies its own.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Checks Wikipedia's robots.txt before each download, failing when it disallows the
+    /// article and spacing downloads by its `Crawl-delay`. A custom `Transport` fetches
+    /// without the check.
+    pub fn respect_robots(mut self) -> Self {
+        self.robots = true;
+        self
+    }
+}
+
+// Shared by every source, so robots.txt is read once per process and its `Crawl-delay`
+// spaces out the downloads of every client
+#[cfg(feature = "blocking")]
+fn robots_limiter() -> &'static HostLimiter {
+    static LIMITER: OnceLock<HostLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| HostLimiter::new(Duration::ZERO).with_robots())
+}
+
+#[cfg(feature = "blocking")]
+impl DataSource for WikipediaSource {
+    fn fetch(&self) -> Result<HashMap<String, CountryInfo>> {
+        self.fetch_with_progress(&|_, _| {})
+    }
+
+    fn fetch_with_progress(&self, progress: &Progress) -> Result<HashMap<String, CountryInfo>> {
+        Ok(self.fetch_with_provenance(progress)?.0)
+    }
+
+    fn fetch_with_provenance(
+        &self,
+        progress: &Progress,
+    ) -> Result<(HashMap<String, CountryInfo>, Option<Provenance>)> {
+        let url = article_url(self.revision);
+        let (tables, mut provenance) = match &self.transport {
+            Some(transport) => download_tables_through(transport.as_ref(), &url, progress)?,
+            None => {
+                if self.robots {
+                    robots_limiter().wait(&url)?;
+                }
+                download_tables_from(&url, self.timeout, progress)?
+            }
+        };
+        // Credited to the article, `Provenance::permalink` pointing at the pinned revision
+        provenance.source_url = FETCH_URL.to_string();
+        provenance.revision = provenance.revision.or(self.revision);
+
+        progress(Phase::Parsing, 0.0);
+        let result = match (&self.table, self.publisher) {
+            (Some(selector), _) => parse_selected(&tables, selector)?,
+            (None, Some(publisher)) => parse_publisher(&tables, publisher)?,
+            (None, None) => parse_countries(&tables)?,
+        };
+        progress(Phase::Parsing, 1.0);
+        tracing::debug!(entries = result.len(), publisher = ?self.publisher, "parsed");
+
+        Ok((result, Some(provenance)))
+    }
+}
+
+/// Never fetches, so the client answers from the cache or the bundled defaults.
+/// The default source when built without the `blocking` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OfflineSource;
+
+impl DataSource for OfflineSource {
+    fn fetch(&self) -> Result<HashMap<String, CountryInfo>> {
+        Err(anyhow!("Offline source, nothing to fetch"))
+    }
+}
@@ -0,0 +1,271 @@
+use crate::html::{extract_tables, selector, text};
+use crate::limits::{check_length, Limited, MAX_PAGE_BYTES};
+use crate::parallel::{join_all, HostLimiter};
+use crate::rate_limit;
+use crate::{get_tmp_dir, read_tmp_file, write_tmp_file, CountryInfo, CountryName, Region};
+use anyhow::{anyhow, Result};
+use scraper::{ElementRef, Html};
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub const SOURCES: &[(&str, &str)] = &[
+    (
+        "United States",
+        "https://en.wikipedia.org/wiki/List_of_U.S._states_and_territories_by_life_expectancy",
+    ),
+    (
+        "United Kingdom",
+        "https://en.wikipedia.org/wiki/List_of_countries_of_the_United_Kingdom_by_life_expectancy",
+    ),
+    (
+        "Canada",
+        "https://en.wikipedia.org/wiki/List_of_Canadian_provinces_and_territories_by_life_expectancy",
+    ),
+    (
+        "Germany",
+        "https://en.wikipedia.org/wiki/List_of_German_states_by_life_expectancy",
+    ),
+    (
+        "China",
+        "https://en.wikipedia.org/wiki/List_of_Chinese_administrative_divisions_by_life_expectancy",
+    ),
+    (
+        "India",
+        "https://en.wikipedia.org/wiki/List_of_Indian_states_and_union_territories_by_life_expectancy",
+    ),
+    (
+        "Russia",
+        "https://en.wikipedia.org/wiki/List_of_federal_subjects_of_Russia_by_life_expectancy",
+    ),
+];
+
+/// How hard multi-page crawls may hit a host, one request per second and two pages at a time by default.
+#[derive(Debug, Clone, Copy)]
+pub struct Politeness {
+    /// Minimum time between two requests to the same host.
+    pub delay: Duration,
+    /// Pages fetched at the same time.
+    pub max_concurrency: usize,
+    /// Check each host's robots.txt before crawling it, honoring its `Crawl-delay` when longer than `delay`.
+    pub respect_robots: bool,
+}
+
+impl Default for Politeness {
+    fn default() -> Self {
+        Politeness {
+            delay: Duration::from_secs(1),
+            max_concurrency: 2,
+            respect_robots: false,
+        }
+    }
+}
+
+impl Politeness {
+    fn limiter(&self) -> HostLimiter {
+        let limiter = HostLimiter::new(self.delay);
+        if self.respect_robots {
+            limiter.with_robots()
+        } else {
+            limiter
+        }
+    }
+}
+
+pub fn get_subnational_data(country: &str) -> Result<HashMap<Region, CountryInfo>> {
+    load_or_fetch(country, &HostLimiter::new(Duration::ZERO))
+}
+
+fn load_or_fetch(country: &str, limiter: &HostLimiter) -> Result<HashMap<Region, CountryInfo>> {
+    let path = get_tmp_dir().join(format!(
+        ".tmp_subnational_{}.json",
+        country.to_lowercase().replace(' ', "_")
+    ));
+    let subdivisions = if let Some(subdivisions) = read_tmp_file(&path)? {
+        subdivisions
+    } else {
+        let subdivisions = fetch_subdivisions(country, source_url(country)?, limiter)?;
+        write_tmp_file(&path, &subdivisions)?;
+        subdivisions
+    };
+
+    Ok(into_regions(country, subdivisions))
+}
+
+/// Every country in `SOURCES`, the pages being fetched concurrently.
+pub fn get_all_subnational() -> Result<HashMap<Region, CountryInfo>> {
+    get_all_subnational_with(&Politeness::default())
+}
+
+pub fn get_all_subnational_with(politeness: &Politeness) -> Result<HashMap<Region, CountryInfo>> {
+    let limiter = &politeness.limiter();
+    merge(join_all(
+        SOURCES
+            .iter()
+            .map(|(country, _)| move || load_or_fetch(country, limiter))
+            .collect(),
+        politeness.max_concurrency,
+    ))
+}
+
+pub fn fetch_all_subnational() -> Result<HashMap<Region, CountryInfo>> {
+    fetch_all_subnational_with(&Politeness::default())
+}
+
+pub fn fetch_all_subnational_with(politeness: &Politeness) -> Result<HashMap<Region, CountryInfo>> {
+    let limiter = &politeness.limiter();
+    merge(join_all(
+        SOURCES
+            .iter()
+            .map(|(country, url)| {
+                move || {
+                    let subdivisions = fetch_subdivisions(country, url, limiter)?;
+                    Ok(into_regions(country, subdivisions))
+                }
+            })
+            .collect(),
+        politeness.max_concurrency,
+    ))
+}
+
+fn merge(
+    results: Vec<Result<HashMap<Region, CountryInfo>>>,
+) -> Result<HashMap<Region, CountryInfo>> {
+    let mut merged = HashMap::new();
+    for result in results {
+        merged.extend(result?);
+    }
+    Ok(merged)
+}
+
+pub fn fetch_subnational(country: &str) -> Result<HashMap<Region, CountryInfo>> {
+    fetch_subnational_from(country, source_url(country)?)
+}
+
+/// Same as `fetch_subnational`, for Wikipedia lists not covered by `SOURCES`.
+pub fn fetch_subnational_from(country: &str, url: &str) -> Result<HashMap<Region, CountryInfo>> {
+    let subdivisions = fetch_subdivisions(country, url, &HostLimiter::new(Duration::ZERO))?;
+    Ok(into_regions(country, subdivisions))
+}
+
+fn source_url(country: &str) -> Result<&'static str> {
+    SOURCES
+        .iter()
+        .find(|(name, _)| *name == country)
+        .map(|(_, url)| *url)
+        .ok_or_else(|| anyhow!("No subnational source for {}", country))
+}
+
+fn into_regions(
+    country: &str,
+    subdivisions: HashMap<String, CountryInfo>,
+) -> HashMap<Region, CountryInfo> {
+    subdivisions
+        .into_iter()
+        .map(|(name, info)| {
+            let region = Region::Subdivision {
+                country: country.to_string(),
+                name,
+            };
+            (region, info)
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Columns {
+    all: Option<usize>,
+    male: Option<usize>,
+    female: Option<usize>,
+}
+
+fn fetch_subdivisions(
+    country: &str,
+    url: &str,
+    limiter: &HostLimiter,
+) -> Result<HashMap<String, CountryInfo>> {
+    limiter.wait(url)?;
+    tracing::debug!(country, url, "fetching subdivisions");
+    let resp = rate_limit::get(url)?;
+    check_length(resp.content_length(), MAX_PAGE_BYTES, "The page")?;
+    let body = Limited::new(resp, MAX_PAGE_BYTES, "The page");
+    let document = Html::parse_document(&extract_tables(body)?);
+
+    // Column layouts differ between lists, use the first table that has a per-sex breakdown
+    for table in document.select(&selector("table.wikitable")) {
+        if let Some(columns) = find_columns(table) {
+            let result = parse_rows(table, &columns);
+            if !result.is_empty() {
+                return Ok(result);
+            }
+        }
+    }
+
+    Err(anyhow!(
+        "No table with a per-sex breakdown found for {}",
+        country
+    ))
+}
+
+fn find_columns(table: ElementRef) -> Option<Columns> {
+    let mut columns = Columns::default();
+    for tr in table.select(&selector("tr")) {
+        let headers: Vec<String> = tr
+            .select(&selector("th"))
+            .map(|th| text(th).to_lowercase())
+            .collect();
+        if !headers.iter().any(|header| header.contains("male")) {
+            continue;
+        }
+        for (index, header) in headers.iter().enumerate() {
+            if header.contains("female") || header.contains("women") {
+                columns.female = columns.female.or(Some(index));
+            } else if header.contains("male") || header.contains("men") {
+                columns.male = columns.male.or(Some(index));
+            } else if ["overall", "both", "total", "all"]
+                .iter()
+                .any(|word| header.contains(word))
+            {
+                columns.all = columns.all.or(Some(index));
+            }
+        }
+    }
+
+    match columns {
+        Columns {
+            all: Some(_),
+            male: Some(_),
+            female: Some(_),
+        } => Some(columns),
+        _ => None,
+    }
+}
+
+fn parse_rows(table: ElementRef, columns: &Columns) -> HashMap<String, CountryInfo> {
+    let mut result = HashMap::new();
+    for tr in table.select(&selector("tr")) {
+        let cells: Vec<String> = tr
+            .select(&selector("td, th"))
+            .map(|cell| text(cell).trim().to_string())
+            .collect();
+        let value = |index: Option<usize>| {
+            cells
+                .get(index?)
+                .and_then(|cell| cell.trim_end_matches('*').parse::<f64>().ok())
+        };
+        let name = tr
+            .select(&selector("a"))
+            .next()
+            .and_then(|a| CountryName::new(&text(a)).ok())
+            .map(CountryName::into_string);
+        if let (Some(name), Some(all), Some(male), Some(female)) = (
+            name,
+            value(columns.all),
+            value(columns.male),
+            value(columns.female),
+        ) {
+            result.insert(name, CountryInfo { all, male, female });
+        }
+    }
+
+    result
+}
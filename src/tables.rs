@@ -0,0 +1,437 @@
+//! Every life expectancy table of the article, labeled with the organization whose figures it lists.
+
+use crate::countries::is_aggregate;
+use crate::html::{selector, text};
+use crate::{calculate_common, extract_country_name, CountryInfo, Error, COMMON};
+#[cfg(feature = "blocking")]
+use crate::{download_tables, get_tmp_dir, read_tmp_file, write_tmp_file};
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Caption or section heading of the table `get_data` reads, matched loosely so a renamed
+/// section or a new year still finds it.
+pub(crate) const MAIN_TABLE: &str = "World Health Organization (2019)";
+
+// Share of `MAIN_TABLE`'s words a label must carry
+const MIN_SCORE: f64 = 0.6;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Publisher {
+    Who,
+    WorldBank,
+    /// UN World Population Prospects.
+    Un,
+    /// A table whose label names none of the above.
+    Other,
+}
+
+impl Publisher {
+    /// Guessed from a table caption or section heading, "WHO (2019)" or "World Bank Group (2022)" for instance.
+    pub fn from_label(label: &str) -> Publisher {
+        let words: Vec<&str> = label
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        let lower = label.to_lowercase();
+        if words.contains(&"WHO") || lower.contains("world health organization") {
+            Publisher::Who
+        } else if lower.contains("world bank") {
+            Publisher::WorldBank
+        } else if words.contains(&"UN")
+            || lower.contains("united nations")
+            || lower.contains("world population prospects")
+        {
+            Publisher::Un
+        } else {
+            Publisher::Other
+        }
+    }
+}
+
+impl FromStr for Publisher {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "who" => Ok(Publisher::Who),
+            "worldbank" | "wb" => Ok(Publisher::WorldBank),
+            "un" | "unitednations" => Ok(Publisher::Un),
+            _ => Err(anyhow!("Unknown publisher: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Publisher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Publisher::Who => write!(f, "WHO"),
+            Publisher::WorldBank => write!(f, "World Bank"),
+            Publisher::Un => write!(f, "UN"),
+            Publisher::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// Which table of the article to read, overriding the caption matching of `get_data` until
+/// a release catches up with a change of the page.
+#[derive(Debug, Clone)]
+pub enum TableSelector {
+    /// The first table whose caption or section heading matches.
+    Caption(Regex),
+    /// The first element matching among the article's `wikitable` tables, each table being
+    /// kept with its caption only.
+    Css(String),
+}
+
+impl TableSelector {
+    pub fn caption(pattern: &str) -> Result<Self> {
+        Ok(TableSelector::Caption(Regex::new(pattern)?))
+    }
+
+    pub fn css(selector: &str) -> Result<Self> {
+        parse_selector(selector)?;
+        Ok(TableSelector::Css(selector.to_string()))
+    }
+
+    #[cfg(feature = "blocking")]
+    fn select<'a>(&self, document: &'a Html) -> Result<ElementRef<'a>> {
+        let found = match self {
+            TableSelector::Caption(pattern) => labeled(document)
+                .into_iter()
+                .find(|(label, _)| pattern.is_match(label))
+                .map(|(_, table)| table),
+            TableSelector::Css(selector) => document.select(&parse_selector(selector)?).next(),
+        };
+        found.ok_or_else(|| not_found(self.to_string(), &labeled(document)))
+    }
+}
+
+impl fmt::Display for TableSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableSelector::Caption(pattern) => write!(f, "caption /{}/", pattern),
+            TableSelector::Css(selector) => write!(f, "selector {:?}", selector),
+        }
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<Selector> {
+    Selector::parse(selector).map_err(|err| anyhow!("Invalid selector {:?}: {}", selector, err))
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Table {
+    /// The caption, or the heading of the section holding the table.
+    pub label: String,
+    pub publisher: Publisher,
+    /// Same shape as `get_data`, "Common" average included.
+    pub data: HashMap<String, CountryInfo>,
+    /// The World and region rows, empty when the table has none.
+    pub regions: HashMap<String, CountryInfo>,
+}
+
+/// Every `wikitable` with at least one country row, in page order. Tables whose rows
+/// aren't life expectancies (or don't parse) are left out rather than failing the rest.
+pub fn parse_tables(html: &str) -> Vec<Table> {
+    let document = Html::parse_document(html);
+    let mut tables = Vec::new();
+    for (label, element) in labeled(&document) {
+        match parse_rows(element) {
+            Ok(mut data) if !data.is_empty() => {
+                data.insert(String::from(COMMON), calculate_common(&data));
+                tables.push(Table {
+                    publisher: Publisher::from_label(&label),
+                    regions: parse_region_rows(element).unwrap_or_default(),
+                    label,
+                    data,
+                });
+            }
+            Ok(_) => {}
+            Err(err) => tracing::debug!(label, "skipping table: {:#}", err),
+        }
+    }
+
+    tables
+}
+
+/// The first table of `publisher`, failing with the labels found when there is none.
+pub fn parse_publisher(html: &str, publisher: Publisher) -> Result<HashMap<String, CountryInfo>> {
+    let tables = parse_tables(html);
+    let labels: Vec<String> = tables.iter().map(|table| table.label.clone()).collect();
+    match tables
+        .into_iter()
+        .find(|table| table.publisher == publisher)
+    {
+        Some(table) => Ok(table.data),
+        None if labels.is_empty() => {
+            Err(Error::Parse(format!("no {} table, no table parsed at all", publisher)).into())
+        }
+        None => Err(Error::Parse(format!(
+            "no {} table among: {}",
+            publisher,
+            labels.join("; ")
+        ))
+        .into()),
+    }
+}
+
+/// The table whose label best matches `MAIN_TABLE`, failing with the labels found when none comes close.
+pub(crate) fn main_table(document: &Html) -> Result<ElementRef<'_>> {
+    let tables = labeled(document);
+    let best = tables
+        .iter()
+        .map(|(label, table)| (score(label), *table))
+        .filter(|(score, _)| *score >= MIN_SCORE)
+        // The first of equally good matches, the page lists its main table first
+        .fold(
+            None,
+            |best: Option<(f64, ElementRef)>, candidate| match best {
+                Some(best) if best.0 >= candidate.0 => Some(best),
+                _ => Some(candidate),
+            },
+        );
+    best.map(|(_, table)| table)
+        .ok_or_else(|| not_found(format!("caption like {:?}", MAIN_TABLE), &tables))
+}
+
+/// Same as `parse_html`, reading the table `selector` picks.
+#[cfg(feature = "blocking")]
+pub(crate) fn parse_selected(
+    html: &str,
+    selector: &TableSelector,
+) -> Result<HashMap<String, CountryInfo>> {
+    let document = Html::parse_document(html);
+    let mut result = parse_rows(selector.select(&document)?)?;
+    if result.is_empty() {
+        return Err(Error::Parse(format!("no country rows in the table of {}", selector)).into());
+    }
+    result.insert(String::from(COMMON), calculate_common(&result));
+
+    Ok(result)
+}
+
+/// The World and region rows of the table `parse_html` reads, as the article states them
+/// rather than averaged by this crate like "Common".
+#[cfg(feature = "blocking")]
+fn parse_regions(html: &str) -> Result<HashMap<String, CountryInfo>> {
+    let document = Html::parse_document(html);
+    let regions = parse_region_rows(main_table(&document)?)?;
+    if regions.is_empty() {
+        return Err(Error::Parse("no World or region rows in the table".to_string()).into());
+    }
+
+    Ok(regions)
+}
+
+/// Cached World and region rows, fetched when the cache is empty or stale. No bundled
+/// defaults to fall back on.
+#[cfg(feature = "blocking")]
+pub fn get_regions() -> Result<HashMap<String, CountryInfo>> {
+    let path = get_tmp_dir().join(".tmp_regions.json");
+    if let Some(regions) = read_tmp_file(&path)? {
+        return Ok(regions);
+    }
+    let regions = fetch_regions()?;
+    write_tmp_file(&path, &regions)?;
+
+    Ok(regions)
+}
+
+#[cfg(feature = "blocking")]
+pub fn fetch_regions() -> Result<HashMap<String, CountryInfo>> {
+    parse_regions(&download_tables(&|_, _| {})?.0)
+}
+
+fn not_found(wanted: String, tables: &[(String, ElementRef)]) -> anyhow::Error {
+    let labels: Vec<&str> = tables.iter().map(|(label, _)| label.as_str()).collect();
+    Error::Parse(format!(
+        "no table with {} among: {}",
+        wanted,
+        if labels.is_empty() {
+            "no tables".to_string()
+        } else {
+            labels.join("; ")
+        }
+    ))
+    .into()
+}
+
+// Each table with its caption, else the heading of its section, else its position
+fn labeled(document: &Html) -> Vec<(String, ElementRef<'_>)> {
+    let mut tables = Vec::new();
+    let mut heading: Option<String> = None;
+    for element in document.select(&selector("h2, h3, h4, table.wikitable")) {
+        if element.value().name() != "table" {
+            heading = Some(heading_text(element));
+            continue;
+        }
+        let label = element
+            .select(&selector("caption"))
+            .next()
+            .map(|caption| text(caption).trim().to_string())
+            .filter(|caption| !caption.is_empty())
+            .or_else(|| heading.clone())
+            .unwrap_or_else(|| format!("Table {}", tables.len() + 1));
+        tables.push((label, element));
+    }
+
+    tables
+}
+
+// Share of `MAIN_TABLE`'s words found in `label`, typos of a letter forgiven. The
+// acronym counts as every word but the year, "WHO (2019)" being how sections are titled.
+fn score(label: &str) -> f64 {
+    let target = words(MAIN_TABLE);
+    let found = words(label);
+    let acronym: String = target
+        .iter()
+        .filter(|word| word.chars().all(char::is_alphabetic))
+        .filter_map(|word| word.chars().next())
+        .collect::<String>()
+        .to_uppercase();
+    // Upper case only, "who" is a common word
+    let has_acronym = label
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word == acronym);
+    let matched = target
+        .iter()
+        .filter(|word| {
+            found.iter().any(|candidate| close(word, candidate))
+                || (has_acronym && word.chars().all(char::is_alphabetic))
+        })
+        .count();
+
+    matched as f64 / target.len() as f64
+}
+
+fn words(label: &str) -> Vec<String> {
+    label
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// Equal, or one edit apart for words long enough that it can't turn one into another
+fn close(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    if a.len().min(b.len()) < 5 || a.len().abs_diff(b.len()) > 1 {
+        return false;
+    }
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    prefix + suffix + 1 >= a.len().max(b.len())
+}
+
+// "[edit]" links trail the heading text in older markup
+fn heading_text(heading: ElementRef) -> String {
+    let heading = text(heading);
+    heading.trim().trim_end_matches("[edit]").trim().to_string()
+}
+
+/// Rows whose first cell links a country followed by the all, male and female values, other rows
+/// (headers, footnotes, aggregates) are skipped. A country row with a missing or non-numeric value fails.
+pub(crate) fn parse_rows(table: ElementRef) -> Result<HashMap<String, CountryInfo>> {
+    select_rows(table, false)
+}
+
+/// The World and region rows of `table`, see `AGGREGATES`.
+pub(crate) fn parse_region_rows(table: ElementRef) -> Result<HashMap<String, CountryInfo>> {
+    select_rows(table, true)
+}
+
+fn select_rows(table: ElementRef, aggregates: bool) -> Result<HashMap<String, CountryInfo>> {
+    let mut result = HashMap::new();
+    let td = selector("td");
+    for tr in table.select(&selector("tr")) {
+        let mut tds = tr.select(&td).take(4);
+        let first = tds.next();
+        // Aggregate rows are often left unlinked
+        let Some(country_name) = extract_country_name(first).or_else(|| {
+            let name = text(first?);
+            is_aggregate(name.trim()).then(|| name.trim().to_string())
+        }) else {
+            continue;
+        };
+        if is_aggregate(&country_name) != aggregates {
+            continue;
+        }
+        let mut value = || -> Result<f64> {
+            let cell = tds
+                .next()
+                .ok_or_else(|| Error::Parse(format!("missing cell for {}", country_name)))?;
+            let cell = text(cell);
+            // "NaN" and "inf" parse as floats too
+            match cell.trim().parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(value),
+                _ => Err(Error::Parse(format!(
+                    "{:?} for {} is not a number",
+                    cell.trim(),
+                    country_name
+                ))
+                .into()),
+            }
+        };
+        let all = value()?;
+        let male = value()?;
+        let female = value()?;
+        result.insert(country_name, CountryInfo { all, male, female });
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_countries;
+
+    fn page(rows: &str) -> String {
+        format!(
+            "<table class=\"wikitable\"><caption>{}</caption>{}</table>",
+            MAIN_TABLE, rows
+        )
+    }
+
+    // The minimized fuzz input: the fourth wikitable has no rows, so no tbody either
+    #[test]
+    fn rowless_table_is_an_error() {
+        let html = "<table class=\"wikitable\"></table>".repeat(4);
+        assert!(parse_countries(&html).is_err());
+        assert!(parse_countries(&page("")).is_err());
+    }
+
+    #[test]
+    fn parses_country_rows() {
+        let html = page(
+            "<tr><th>Country</th><th>All</th><th>Male</th><th>Female</th></tr>\
+             <tr><td><a>Japan</a></td><td>84.3</td><td>81.5</td><td>86.9</td></tr>\
+             <tr><td><a>Chad</a></td><td>52.5</td><td>50.9</td><td>54.2</td></tr>",
+        );
+        let data = parse_countries(&html).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data["Japan"].female, 86.9);
+        assert!(data.contains_key(COMMON));
+    }
+
+    #[test]
+    fn non_finite_value_is_an_error() {
+        let html = page("<tr><td><a>Japan</a></td><td>NaN</td><td>81.5</td><td>86.9</td></tr>");
+        assert!(parse_countries(&html).is_err());
+    }
+}
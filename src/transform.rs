@@ -0,0 +1,20 @@
+//! Changes applied to freshly fetched data before it is cached, company-specific corrections
+//! for instance. See `ClientBuilder::transform`.
+
+use crate::CountryInfo;
+use anyhow::Result;
+use std::collections::HashMap;
+
+pub trait Transform: Send + Sync {
+    /// The data to cache instead of `data`, a failure failing the fetch.
+    fn apply(&self, data: HashMap<String, CountryInfo>) -> Result<HashMap<String, CountryInfo>>;
+}
+
+impl<F> Transform for F
+where
+    F: Fn(HashMap<String, CountryInfo>) -> Result<HashMap<String, CountryInfo>> + Send + Sync,
+{
+    fn apply(&self, data: HashMap<String, CountryInfo>) -> Result<HashMap<String, CountryInfo>> {
+        self(data)
+    }
+}
@@ -0,0 +1,39 @@
+//! How pages are downloaded. `WikipediaSource` goes through `reqwest` unless given another
+//! `Transport`, to stub responses in tests or route through an embedder's own HTTP stack.
+
+#[cfg(feature = "blocking")]
+use crate::limits::{check_length, Limited, MAX_PAGE_BYTES};
+use anyhow::Result;
+use bytes::Bytes;
+#[cfg(feature = "blocking")]
+use std::io::Read;
+
+pub trait Transport: Send + Sync {
+    /// Body of a successful GET, an error status is an error.
+    fn get(&self, url: &str) -> Result<Bytes>;
+}
+
+impl<F> Transport for F
+where
+    F: Fn(&str) -> Result<Bytes> + Send + Sync,
+{
+    fn get(&self, url: &str) -> Result<Bytes> {
+        self(url)
+    }
+}
+
+/// `reqwest::blocking`, honoring the usual `HTTPS_PROXY` variables and short `Retry-After` waits.
+#[cfg(feature = "blocking")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReqwestTransport;
+
+#[cfg(feature = "blocking")]
+impl Transport for ReqwestTransport {
+    fn get(&self, url: &str) -> Result<Bytes> {
+        let resp = crate::rate_limit::get(url)?;
+        check_length(resp.content_length(), MAX_PAGE_BYTES, "The response")?;
+        let mut body = Vec::new();
+        Limited::new(resp, MAX_PAGE_BYTES, "The response").read_to_end(&mut body)?;
+        Ok(Bytes::from(body))
+    }
+}
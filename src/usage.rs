@@ -0,0 +1,93 @@
+//! Opt-in usage counters, kept in `usage.json` under the cache directory and never sent anywhere.
+//! Nothing is counted until `enable` creates the file, `disable` removes it again.
+
+use crate::date::iso_date;
+use crate::get_tmp_dir;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+pub(crate) const FILE_NAME: &str = "usage.json";
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Counters {
+    /// Date counting started, `YYYY-MM-DD`.
+    pub since: String,
+    /// Requests to the data source, failed ones included.
+    pub fetches: u64,
+    pub fetch_failures: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// Answers served from the bundled defaults after a failed fetch.
+    pub fallbacks: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Event {
+    Fetch,
+    FetchFailure,
+    CacheHit,
+    CacheMiss,
+    Fallback,
+}
+
+fn path() -> PathBuf {
+    get_tmp_dir().join(FILE_NAME)
+}
+
+pub fn is_enabled() -> bool {
+    path().is_file()
+}
+
+/// Starts counting from zero, also when counting was already enabled.
+pub fn enable() -> Result<()> {
+    write(&Counters {
+        since: iso_date(SystemTime::now()),
+        ..Counters::default()
+    })
+}
+
+pub fn disable() -> Result<()> {
+    if is_enabled() {
+        fs::remove_file(path())?;
+    }
+    Ok(())
+}
+
+/// Counts so far, `None` unless enabled.
+pub fn counters() -> Result<Option<Counters>> {
+    if !is_enabled() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path())?)?))
+}
+
+fn write(counters: &Counters) -> Result<()> {
+    fs::create_dir_all(get_tmp_dir())?;
+    fs::write(path(), serde_json::to_string_pretty(counters)?)?;
+    Ok(())
+}
+
+// Counting never fails the caller, and concurrent processes may lose an increment
+pub(crate) fn record(event: Event) {
+    let update = || -> Result<()> {
+        let Some(mut counters) = counters()? else {
+            return Ok(());
+        };
+        let counter = match event {
+            Event::Fetch => &mut counters.fetches,
+            Event::FetchFailure => &mut counters.fetch_failures,
+            Event::CacheHit => &mut counters.cache_hits,
+            Event::CacheMiss => &mut counters.cache_misses,
+            Event::Fallback => &mut counters.fallbacks,
+        };
+        *counter += 1;
+        write(&counters)
+    };
+    if let Err(err) = update() {
+        tracing::debug!("usage counters not updated: {:#}", err);
+    }
+}